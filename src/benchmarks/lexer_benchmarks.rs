@@ -7,9 +7,9 @@ fn lexer_benchmark(c: &mut Criterion) {
     let mut test = String::new();
 
     script.push_str(": ; . = == =+ =- =* =/ + ++ += - -- -= ! != * / /= \\ % $ @ # ^ ( ) { } [ ] , > >= < <= & && | || ");
-    script.push_str("int float string bool char array null ");
+    script.push_str("int float string bool char array object null ");
     script.push_str("variableName anotherVar _privateVar var0123 ");
-    script.push_str("if else for while switch case fn writeLn convertTo ");
+    script.push_str("if else for while switch case default fn return break continue try catch finally throw writeLn convertTo ");
     script.push_str("123 123.456 'a' \"string\" true false ");
     println!("The following script will be repeated 1000 times and tokenized by the lexer: {}", script);
 
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::type_checker::TypeChecker;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::error::Error;
+use crate::types::into_native_function::IntoNativeFunction;
+use crate::types::limits::Limits;
+use crate::types::native_function::NativeFunction;
+use crate::types::permissions::Permissions;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::scope::Scope;
+use crate::types::script::Script;
+use crate::types::signature::Signature;
+
+type PrintHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Compiles and runs scripts on behalf of a host application.
+///
+/// An engine holds host configuration only; every run starts from a fresh interpreter
+/// whose globals come from the [`Scope`] passed in.
+/// Engines and compiled scripts are `Send + Sync`, so one engine can run scripts on several threads at once.
+#[derive(Default)]
+pub struct Engine {
+    print_handler: Option<PrintHandler>,
+    natives: HashMap<String, NativeFunction>,
+    signatures: HashMap<String, Signature>, // For typed host functions, so calls are checked at compile time
+    limits: Limits,
+    requirements: HashMap<String, Vec<String>>,
+    permissions: Permissions,
+}
+
+impl Engine {
+    /// Creates an engine whose `writeLn` prints to standard output.
+    pub fn new() -> Self {
+        Engine {
+            print_handler: None,
+            natives: HashMap::new(),
+            signatures: HashMap::new(),
+            limits: Limits::default(),
+            requirements: HashMap::new(),
+            permissions: Permissions::default(),
+        }
+    }
+
+    /// Sends every line written by `writeLn` to `handler` instead of standard output.
+    ///
+    /// The handler must be `Send + Sync` so that the engine can be shared between threads.
+    pub fn on_print<F: Fn(&str) + Send + Sync + 'static>(&mut self, handler: F) -> &mut Self {
+        self.print_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets the resource budgets applied to every run. See [`Limits`] for the defaults.
+    pub fn set_limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the capabilities granted to every run. See [`Permissions`] for the default.
+    pub fn set_permissions(&mut self, permissions: Permissions) -> &mut Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Declares that calling the host function `function` needs `capability`.
+    ///
+    /// A function may need several capabilities, and requirements may be declared before
+    /// or after the function is registered. Calls without a granted capability fail with
+    /// [`RuntimeErrorKind::PermissionDenied`](crate::RuntimeErrorKind::PermissionDenied).
+    ///
+    /// ```
+    /// use safe_script::{DynamicValue, Engine, Error, Permissions, RuntimeErrorKind, Scope};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine
+    ///     .register_fn("sendAlert", |_| Ok(DynamicValue::Null))
+    ///     .require_capability("sendAlert", "net");
+    ///
+    /// let denied = engine.eval("sendAlert(\"disk full\");", &mut Scope::new());
+    /// assert!(matches!(denied, Err(Error::Runtime(error)) if matches!(error.kind, RuntimeErrorKind::PermissionDenied { .. })));
+    ///
+    /// engine.set_permissions(Permissions::default().grant("net"));
+    /// assert!(engine.eval("sendAlert(\"disk full\");", &mut Scope::new()).is_ok());
+    /// ```
+    pub fn require_capability(&mut self, function: &str, capability: &str) -> &mut Self {
+        self.requirements.entry(function.to_string()).or_default().push(capability.to_string());
+        self
+    }
+
+    /// Registers a host function that scripts call like any other function.
+    ///
+    /// The function receives the evaluated arguments as written and checks them itself.
+    /// Returning `Err("message".into())` fails the script with [`RuntimeErrorKind::Native`](crate::RuntimeErrorKind::Native).
+    /// Registering a name again replaces the earlier function, and scripts cannot declare
+    /// a `fn` with the same name.
+    pub fn register_fn<F>(&mut self, name: &str, function: F) -> &mut Self
+    where
+        F: Fn(&[DynamicValue]) -> Result<DynamicValue, RuntimeError> + Send + Sync + 'static,
+    {
+        self.natives.insert(name.to_string(), Arc::new(function));
+        self.signatures.remove(name);
+        self
+    }
+
+    /// Registers a host function with typed arguments, converted from script values before the call.
+    ///
+    /// ```
+    /// use safe_script::{Engine, Scope};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_typed_fn("area", |width: f64, height: f64| width * height);
+    ///
+    /// let result = engine.eval("area(3, 1.5);", &mut Scope::new()).unwrap();
+    /// assert_eq!(result.as_float(), Some(4.5));
+    /// ```
+    ///
+    /// Calls are checked against the argument types when the script compiles, so a wrong
+    /// argument count or a mismatched literal is reported by [`Engine::compile`]. Values only
+    /// known at run time are converted before the function runs, failing the script if they
+    /// cannot be. Host functions take positional arguments only.
+    pub fn register_typed_fn<Args, F: IntoNativeFunction<Args>>(&mut self, name: &str, function: F) -> &mut Self {
+        self.natives.insert(name.to_string(), function.into_native_function(name));
+        self.signatures.insert(name.to_string(), Signature::positional(F::parameter_types()));
+        self
+    }
+
+    /// Lexes, parses and type checks `source` into a reusable [`Script`].
+    ///
+    /// The type check knows the declared types of script variables and the functions
+    /// registered so far. Host globals and untyped values are checked when the script runs.
+    pub fn compile(&self, source: &str) -> Result<Script, Error> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let ast = Parser::new(tokens).parse()?;
+        let mut checker = TypeChecker::new();
+        for name in self.natives.keys() {
+            checker.register_native(name, self.signatures.get(name).cloned());
+        }
+        checker.check(&ast)?;
+        Ok(Script { ast })
+    }
+
+    /// Runs a compiled script against `scope`, returning the value of its last expression statement.
+    ///
+    /// Globals in `scope` are updated even when the script fails part way through.
+    pub fn run(&self, script: &Script, scope: &mut Scope) -> Result<DynamicValue, Error> {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_limits(self.limits.clone());
+        interpreter.set_permissions(self.permissions.clone());
+        for (function, capabilities) in &self.requirements {
+            for capability in capabilities {
+                interpreter.require_capability(function, capability);
+            }
+        }
+        for (name, function) in &self.natives {
+            interpreter.register_native(name, Arc::clone(function));
+        }
+        if let Some(handler) = &self.print_handler {
+            let handler = Arc::clone(handler);
+            interpreter.set_print_handler(move |line| handler(line));
+        }
+        Ok(interpreter.run_with_globals(&script.ast, &mut scope.variables)?)
+    }
+
+    /// Compiles and runs `source` in one step.
+    pub fn eval(&self, source: &str, scope: &mut Scope) -> Result<DynamicValue, Error> {
+        let script = self.compile(source)?;
+        self.run(&script, scope)
+    }
+}
@@ -0,0 +1,1242 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use crate::types::argument::Argument;
+use crate::types::assignment_operator::AssignmentOperator;
+use crate::types::ast::Ast;
+use crate::types::ast_node::AstNode;
+use crate::types::base_functions::BaseFunctions;
+use crate::types::binary_operator::BinaryOperator;
+use crate::types::case_label::CaseLabel;
+use crate::types::closure::Closure;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::expression::Expression;
+use crate::types::flow::Flow;
+use crate::types::format_spec::FormatSpec;
+use crate::types::function_definition::FunctionDefinition;
+use crate::types::interpolation_segment::InterpolationSegment;
+use crate::types::limits::Limits;
+use crate::types::native_function::NativeFunction;
+use crate::types::numeric::Numeric;
+use crate::types::permissions::Permissions;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::stack_frame::StackFrame;
+use crate::types::switch_case::SwitchCase;
+use crate::types::unary_operator::UnaryOperator;
+use crate::types::var_type::VarType;
+use crate::types::variable::Variable;
+
+pub struct Interpreter {
+    scopes: Vec<HashMap<String, Variable>>, // scopes[0] holds the globals
+    functions: HashMap<String, Arc<FunctionDefinition>>,
+    natives: HashMap<String, NativeFunction>,
+    requirements: HashMap<String, Vec<String>>, // Capabilities each native function needs
+    permissions: Permissions,
+    call_stack: Vec<StackFrame>,
+    print_handler: Box<dyn FnMut(&str)>,
+    limits: Limits,
+    operations: u64,
+    memory: usize,
+    depth: usize, // Statements and expressions in progress, across calls
+    deadline: Option<Instant>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            natives: HashMap::new(),
+            requirements: HashMap::new(),
+            permissions: Permissions::default(),
+            call_stack: Vec::new(),
+            print_handler: Box::new(|line| println!("{}", line)),
+            limits: Limits::default(),
+            operations: 0,
+            memory: 0,
+            depth: 0,
+            deadline: None,
+        }
+    }
+
+    pub fn set_print_handler<F: FnMut(&str) + 'static>(&mut self, handler: F) {
+        self.print_handler = Box::new(handler);
+    }
+
+    pub fn register_native(&mut self, name: &str, function: NativeFunction) {
+        self.natives.insert(name.to_string(), function);
+    }
+
+    pub fn require_capability(&mut self, function: &str, capability: &str) {
+        self.requirements.entry(function.to_string()).or_default().push(capability.to_string());
+    }
+
+    pub fn set_permissions(&mut self, permissions: Permissions) {
+        self.permissions = permissions;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    // Runs a whole program, returning the value of its final expression statement
+    pub fn run(&mut self, ast: &Ast) -> Result<DynamicValue, RuntimeError> {
+        self.operations = 0;
+        self.memory = 0;
+        self.depth = 0;
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+        self.hoist_functions(&ast.statements)?;
+        let mut result = DynamicValue::Null;
+        for statement in &ast.statements {
+            // The parser only allows break, continue and return inside their constructs
+            match self.execute(statement)? {
+                Flow::Normal(value) => result = value,
+                Flow::Return(value) => return Ok(value),
+                Flow::Break | Flow::Continue => break,
+            }
+        }
+        Ok(result)
+    }
+
+    // Runs a program with the host's globals swapped in as scopes[0], handing them back afterwards
+    pub fn run_with_globals(&mut self, ast: &Ast, globals: &mut HashMap<String, Variable>) -> Result<DynamicValue, RuntimeError> {
+        std::mem::swap(&mut self.scopes[0], globals);
+        let result = self.run(ast);
+        std::mem::swap(&mut self.scopes[0], globals);
+        result
+    }
+
+    // Statements
+
+    fn hoist_functions(&mut self, statements: &[Spanned<AstNode>]) -> Result<(), RuntimeError> {
+        let mut seen = Vec::new();
+        for statement in statements {
+            if let AstNode::Function { name, .. } = &statement.node {
+                if seen.contains(&name) {
+                    let error = RuntimeError::new(RuntimeErrorKind::AlreadyDeclared(name.clone()));
+                    return Err(error.with_span(statement.span));
+                }
+                seen.push(name);
+                self.define_function(&statement.node).map_err(|error| error.with_span(statement.span))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Scripts may not replace host functions, wherever the declaration sits
+    fn define_function(&mut self, node: &AstNode) -> Result<(), RuntimeError> {
+        if let AstNode::Function { name, signature, body, .. } = node {
+            if self.natives.contains_key(name) {
+                return Err(RuntimeError::new(RuntimeErrorKind::AlreadyDeclared(name.clone())));
+            }
+            self.functions.insert(name.clone(), Arc::new(FunctionDefinition {
+                name: name.clone(),
+                signature: signature.clone(),
+                body: body.clone(),
+            }));
+        }
+        Ok(())
+    }
+
+    // Executes one statement; expression statements yield their value, everything else yields Null
+    fn execute(&mut self, statement: &Spanned<AstNode>) -> Result<Flow, RuntimeError> {
+        self.tick().and_then(|_| self.nest()).map_err(|error| error.with_span(statement.span))?;
+        let flow = self.execute_node(&statement.node).map_err(|error| error.with_span(statement.span));
+        self.depth -= 1;
+        flow
+    }
+
+    // Larger arms live in their own functions, as with evaluate_node
+    fn execute_node(&mut self, node: &AstNode) -> Result<Flow, RuntimeError> {
+        match node {
+            AstNode::VarDeclaration { var_type, name, value } => self.execute_declaration(var_type, name, value.as_ref()),
+            AstNode::Expression(expression) => self.evaluate(expression).map(Flow::Normal),
+            AstNode::Block(statements) => self.execute_block(statements),
+            AstNode::If { condition, then_branch, else_branch } => self.execute_if(condition, then_branch, else_branch.as_deref()),
+            AstNode::While { condition, body } => self.execute_while(condition, body),
+            AstNode::For { initializer, condition, update, body } => {
+                self.scopes.push(HashMap::new());
+                let result = self.execute_for(initializer.as_deref(), condition.as_ref(), update.as_ref(), body);
+                self.scopes.pop();
+                result
+            },
+            AstNode::Switch { subject, cases, default } => {
+                match self.execute_switch(subject, cases, default.as_deref())? {
+                    // A break inside a case leaves the switch, not an enclosing loop
+                    Flow::Break => Ok(Flow::Normal(DynamicValue::Null)),
+                    flow => Ok(flow),
+                }
+            },
+            AstNode::Function { .. } => {
+                self.define_function(node)?;
+                Ok(Flow::Normal(DynamicValue::Null))
+            },
+            AstNode::Return(value) => {
+                let value = match value {
+                    Some(expression) => self.evaluate(expression)?,
+                    None => DynamicValue::Null,
+                };
+                Ok(Flow::Return(value))
+            },
+            AstNode::Break => Ok(Flow::Break),
+            AstNode::Continue => Ok(Flow::Continue),
+            AstNode::Try { body, catch, finally } => self.execute_try(body, catch.as_ref(), finally.as_deref()),
+            AstNode::Throw(value) => Err(self.evaluate_thrown(value)),
+        }
+    }
+
+    fn execute_declaration(&mut self, var_type: &VarType, name: &str, value: Option<&Spanned<Expression>>) -> Result<Flow, RuntimeError> {
+        let value = match value {
+            Some(expression) => {
+                let value = self.evaluate(expression)?;
+                coerce(value, var_type)?
+            },
+            None => default_value(var_type),
+        };
+        self.declare(name, Some(var_type.clone()), value)?;
+        Ok(Flow::Normal(DynamicValue::Null))
+    }
+
+    fn execute_if(
+        &mut self,
+        condition: &Spanned<Expression>,
+        then_branch: &Spanned<AstNode>,
+        else_branch: Option<&Spanned<AstNode>>,
+    ) -> Result<Flow, RuntimeError> {
+        if self.evaluate_condition(condition)? {
+            self.execute_scoped(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute_scoped(else_branch)
+        } else {
+            Ok(Flow::Normal(DynamicValue::Null))
+        }
+    }
+
+    fn execute_while(&mut self, condition: &Spanned<Expression>, body: &Spanned<AstNode>) -> Result<Flow, RuntimeError> {
+        while self.evaluate_condition(condition)? {
+            match self.execute_scoped(body)? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Normal(_) | Flow::Continue => {},
+            }
+        }
+        Ok(Flow::Normal(DynamicValue::Null))
+    }
+
+    // Runs statements in a new scope, stopping early at the first break, continue or return
+    fn execute_block(&mut self, statements: &[Spanned<AstNode>]) -> Result<Flow, RuntimeError> {
+        self.scopes.push(HashMap::new());
+        let result = self.execute_statements(statements);
+        self.scopes.pop();
+        result
+    }
+
+    fn execute_statements(&mut self, statements: &[Spanned<AstNode>]) -> Result<Flow, RuntimeError> {
+        for statement in statements {
+            match self.execute(statement)? {
+                Flow::Normal(_) => {},
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal(DynamicValue::Null))
+    }
+
+    // Runs a branch or loop body in its own scope, even when it is a single statement
+    fn execute_scoped(&mut self, node: &Spanned<AstNode>) -> Result<Flow, RuntimeError> {
+        match &node.node {
+            AstNode::Block(statements) => self.execute_block(statements),
+            _ => self.execute_block(std::slice::from_ref(node)),
+        }
+    }
+
+    fn execute_for(
+        &mut self,
+        initializer: Option<&Spanned<AstNode>>,
+        condition: Option<&Spanned<Expression>>,
+        update: Option<&Spanned<Expression>>,
+        body: &Spanned<AstNode>,
+    ) -> Result<Flow, RuntimeError> {
+        if let Some(initializer) = initializer {
+            self.execute(initializer)?;
+        }
+        loop {
+            if let Some(condition) = condition {
+                if !self.evaluate_condition(condition)? {
+                    break;
+                }
+            }
+            match self.execute_scoped(body)? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                // The update still runs after a continue
+                Flow::Normal(_) | Flow::Continue => {},
+            }
+            if let Some(update) = update {
+                self.evaluate(update)?;
+            }
+        }
+        Ok(Flow::Normal(DynamicValue::Null))
+    }
+
+    // Runs the first matching case only, or the default when none match; cases never fall through
+    fn execute_switch(
+        &mut self,
+        subject: &Spanned<Expression>,
+        cases: &[SwitchCase],
+        default: Option<&[Spanned<AstNode>]>,
+    ) -> Result<Flow, RuntimeError> {
+        let subject = self.evaluate(subject)?;
+        for case in cases {
+            for label in &case.labels {
+                if self.label_matches(&subject, label)? {
+                    return self.execute_block(&case.body);
+                }
+            }
+        }
+        match default {
+            Some(body) => self.execute_block(body),
+            None => Ok(Flow::Normal(DynamicValue::Null)),
+        }
+    }
+
+    // A finally block runs last, unless a limit was exceeded. A break, continue, return or error inside it
+    // replaces the outcome of the rest
+    fn execute_try(
+        &mut self,
+        body: &[Spanned<AstNode>],
+        catch: Option<&(String, Vec<Spanned<AstNode>>)>,
+        finally: Option<&[Spanned<AstNode>]>,
+    ) -> Result<Flow, RuntimeError> {
+        let result = match (self.execute_block(body), catch) {
+            (Err(error), Some((name, handler))) if error.kind.is_catchable() => {
+                let error = self.traced(error);
+                self.scopes.push(HashMap::from([(name.clone(), Variable {
+                    var_type: Some(VarType::Error),
+                    value: DynamicValue::Error(Box::new(error)),
+                })]));
+                let result = self.execute_block(handler);
+                self.scopes.pop();
+                result
+            },
+            (result, _) => result,
+        };
+        if matches!(&result, Err(error) if !error.kind.is_catchable()) {
+            return result;
+        }
+        match finally.map(|finally| self.execute_block(finally)).transpose()? {
+            Some(flow @ (Flow::Break | Flow::Continue | Flow::Return(_))) => Ok(flow),
+            _ => result,
+        }
+    }
+
+    // Throwing a message raises a new error, while rethrowing a caught error keeps its kind, location and backtrace
+    fn evaluate_thrown(&mut self, value: &Spanned<Expression>) -> RuntimeError {
+        match self.evaluate(value) {
+            Ok(DynamicValue::String(message)) => RuntimeError::new(RuntimeErrorKind::Thrown(message)),
+            Ok(DynamicValue::Error(error)) => *error,
+            Ok(other) => invalid_operation(&format!("Cannot throw {:?}", other.get_type())),
+            Err(error) => error,
+        }
+    }
+
+    fn label_matches(&mut self, subject: &DynamicValue, label: &CaseLabel) -> Result<bool, RuntimeError> {
+        match label {
+            CaseLabel::Value(value) => Ok(values_equal(subject, &self.evaluate(value)?)),
+            CaseLabel::Range { start, end } => {
+                let (low, high) = (self.evaluate(start)?, self.evaluate(end)?);
+                in_range(subject, &low, &high).map_err(|error| error.with_span(label.span()))
+            },
+        }
+    }
+
+    fn evaluate_condition(&mut self, condition: &Spanned<Expression>) -> Result<bool, RuntimeError> {
+        match self.evaluate(condition)? {
+            DynamicValue::Bool(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Bool, &other).with_span(condition.span)),
+        }
+    }
+
+    // Expressions
+
+    fn evaluate(&mut self, expression: &Spanned<Expression>) -> Result<DynamicValue, RuntimeError> {
+        self.tick().and_then(|_| self.nest()).map_err(|error| error.with_span(expression.span))?;
+        let value = self.evaluate_node(&expression.node, expression.span)
+            .and_then(|value| self.charge(&value).map(|_| value))
+            .map_err(|error| error.with_span(expression.span));
+        self.depth -= 1;
+        value
+    }
+
+    // Larger arms live in their own functions, which keeps this frame small since every nested call passes through it
+    fn evaluate_node(&mut self, expression: &Expression, span: Span) -> Result<DynamicValue, RuntimeError> {
+        match expression {
+            Expression::Number(Numeric::Int(value)) => Ok(DynamicValue::Int(*value)),
+            Expression::Number(Numeric::Float(value)) => Ok(DynamicValue::Float(*value)),
+            Expression::Number(Numeric::Byte(value)) => Ok(DynamicValue::Byte(*value)),
+            Expression::StringLiteral(value) => Ok(DynamicValue::String(value.clone())),
+            Expression::Interpolated(segments) => self.evaluate_interpolated(segments),
+            Expression::Bool(value) => Ok(DynamicValue::Bool(*value)),
+            Expression::Char(value) => Ok(DynamicValue::Char(*value)),
+            Expression::Null => Ok(DynamicValue::Null),
+            Expression::Identifier(name) => self.evaluate_identifier(name),
+            Expression::Array(elements) => self.evaluate_array(elements),
+            Expression::Object(entries) => self.evaluate_object(entries),
+            Expression::Index { .. } | Expression::Member { .. } => self.evaluate_element(expression),
+            Expression::Unary { operator, operand } => self.evaluate_unary(operator, operand),
+            Expression::Binary { first, rest } => self.evaluate_binary(first, rest),
+            Expression::Assign { target, operator, value } => self.evaluate_assign(target, operator, value),
+            Expression::Call { name, arguments } => {
+                let (arguments, names) = self.evaluate_arguments(arguments)?;
+                self.call_function(name, arguments, &names, span)
+            },
+            Expression::Invoke { target, arguments } => self.evaluate_invoke(target, arguments, span),
+            Expression::Lambda(function) => self.evaluate_lambda(function),
+            Expression::BaseCall { function, arguments } => self.call_base_function(function, arguments),
+            Expression::ConvertTo { value, target } => self.evaluate(value)?.convert_to(target.clone()),
+        }
+    }
+
+    fn evaluate_identifier(&self, name: &str) -> Result<DynamicValue, RuntimeError> {
+        match self.lookup(name) {
+            Ok(variable) => Ok(variable.value.clone()),
+            // A named function can be passed around like a lambda
+            Err(error) => match self.functions.get(name) {
+                Some(function) => Ok(DynamicValue::Function(Arc::new(Closure { function: Arc::clone(function), captured: HashMap::new() }))),
+                None => Err(error),
+            },
+        }
+    }
+
+    fn evaluate_array(&mut self, elements: &[Spanned<Expression>]) -> Result<DynamicValue, RuntimeError> {
+        let values = elements.iter().map(|element| self.evaluate(element)).collect::<Result<Vec<_>, _>>()?;
+        self.check_nesting(DynamicValue::Array(values), 0)
+    }
+
+    fn evaluate_object(&mut self, entries: &[(String, Spanned<Expression>)]) -> Result<DynamicValue, RuntimeError> {
+        let mut values = HashMap::with_capacity(entries.len());
+        for (key, value) in entries {
+            values.insert(key.clone(), self.evaluate(value)?);
+        }
+        self.check_nesting(DynamicValue::Object(values), 0)
+    }
+
+    fn evaluate_element(&mut self, expression: &Expression) -> Result<DynamicValue, RuntimeError> {
+        // Elements of a variable are read in place rather than copying the whole collection
+        if expression.is_place() {
+            let mut keys = Vec::new();
+            let name = self.place_path(expression, &mut keys)?;
+            return self.read_place(name, &keys);
+        }
+        match expression {
+            Expression::Index { target, index } => {
+                let target = self.evaluate(target)?;
+                let key = self.evaluate(index)?;
+                Ok(element(&target, &key)?.into_owned())
+            },
+            Expression::Member { target, name } => {
+                let target = self.evaluate(target)?;
+                Ok(element(&target, &DynamicValue::String(name.clone()))?.into_owned())
+            },
+            _ => unreachable!("only called for indexing and member access"),
+        }
+    }
+
+    fn evaluate_lambda(&self, function: &Arc<FunctionDefinition>) -> Result<DynamicValue, RuntimeError> {
+        // Locals are copied when the lambda is created, while globals stay shared with the rest of the script
+        let captured = self.scopes[1..].iter().flatten().map(|(name, variable)| (name.clone(), variable.clone())).collect();
+        self.check_nesting(DynamicValue::Function(Arc::new(Closure { function: Arc::clone(function), captured })), 0)
+    }
+
+    // Evaluates arguments left to right, along with the name of each, or None for positional ones
+    fn evaluate_arguments<'a>(&mut self, arguments: &'a [Argument]) -> Result<(Vec<DynamicValue>, Vec<Option<&'a str>>), RuntimeError> {
+        let names = arguments.iter().map(|argument| argument.name.as_deref()).collect();
+        let values = arguments
+            .iter()
+            .map(|argument| self.evaluate(&argument.value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((values, names))
+    }
+
+    fn evaluate_invoke(&mut self, target: &Spanned<Expression>, arguments: &[Argument], span: Span) -> Result<DynamicValue, RuntimeError> {
+        let target = self.evaluate(target)?;
+        let (arguments, names) = self.evaluate_arguments(arguments)?;
+        match target {
+            DynamicValue::Function(closure) => self.call_script(&closure.function, Some(&closure.captured), arguments, &names, span),
+            other => Err(invalid_operation(&format!("Cannot call {:?}", other.get_type()))),
+        }
+    }
+
+    fn evaluate_interpolated(&mut self, segments: &[InterpolationSegment]) -> Result<DynamicValue, RuntimeError> {
+        let mut text = String::new();
+        for segment in segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => text.push_str(literal),
+                InterpolationSegment::Hole { expression, format } => {
+                    let value = self.evaluate(expression)?;
+                    let formatted = match format {
+                        Some(spec) => format_with_spec(&value, spec).map_err(|error| error.with_span(expression.span))?,
+                        None => value.to_string(),
+                    };
+                    text.push_str(&formatted);
+                },
+            }
+        }
+        Ok(DynamicValue::String(text))
+    }
+
+    fn evaluate_assign(
+        &mut self,
+        target: &Spanned<Expression>,
+        operator: &AssignmentOperator,
+        value: &Spanned<Expression>,
+    ) -> Result<DynamicValue, RuntimeError> {
+        let mut keys = Vec::new();
+        let name = self.place_path(&target.node, &mut keys)?;
+        let value = self.evaluate(value)?;
+        let value = match compound_operator(operator) {
+            Some(operator) => binary_operation(&self.read_place(name, &keys)?, &operator, &value)?,
+            None => value,
+        };
+        self.write_place(name, &keys, value)
+    }
+
+    // Evaluates a chain link by link from the left. Every link but the last, which evaluate
+    // handles, is ticked, charged and located as if it were an expression of its own
+    fn evaluate_binary(&mut self, first: &Spanned<Expression>, rest: &[(BinaryOperator, Spanned<Expression>)]) -> Result<DynamicValue, RuntimeError> {
+        let ((operator, right), links) = rest.split_last().expect("a chain has at least one link");
+        let mut value = self.evaluate(first)?;
+        let mut value_span = first.span;
+        for (operator, right) in links {
+            let span = first.span.to(right.span);
+            value = self.tick()
+                .and_then(|_| self.evaluate_link(value, value_span, operator, right))
+                .and_then(|value| self.charge(&value).map(|_| value))
+                .map_err(|error| error.with_span(span))?;
+            value_span = span;
+        }
+        self.evaluate_link(value, value_span, operator, right)
+    }
+
+    // And and Or only evaluate their right side when it decides the result
+    fn evaluate_link(
+        &mut self,
+        left: DynamicValue,
+        left_span: Span,
+        operator: &BinaryOperator,
+        right: &Spanned<Expression>,
+    ) -> Result<DynamicValue, RuntimeError> {
+        match (operator, left) {
+            (BinaryOperator::And, DynamicValue::Bool(false)) => Ok(DynamicValue::Bool(false)),
+            (BinaryOperator::Or, DynamicValue::Bool(true)) => Ok(DynamicValue::Bool(true)),
+            (BinaryOperator::And | BinaryOperator::Or, DynamicValue::Bool(_)) => Ok(DynamicValue::Bool(self.evaluate_condition(right)?)),
+            (BinaryOperator::And | BinaryOperator::Or, other) => Err(type_mismatch(VarType::Bool, &other).with_span(left_span)),
+            (_, left) => {
+                let right = self.evaluate(right)?;
+                binary_operation(&left, operator, &right)
+            },
+        }
+    }
+
+    fn evaluate_unary(&mut self, operator: &UnaryOperator, operand: &Spanned<Expression>) -> Result<DynamicValue, RuntimeError> {
+        match operator {
+            UnaryOperator::Not | UnaryOperator::Negate | UnaryOperator::Plus => {
+                let value = self.evaluate(operand)?;
+                unary_operation(operator, value)
+            },
+            _ => self.evaluate_step(operator, operand),
+        }
+    }
+
+    // Increments and decrements, which write back to the variable or element they read
+    fn evaluate_step(&mut self, operator: &UnaryOperator, operand: &Spanned<Expression>) -> Result<DynamicValue, RuntimeError> {
+        let mut keys = Vec::new();
+        let name = self.place_path(&operand.node, &mut keys)?;
+        let old = self.read_place(name, &keys)?;
+        let step = match operator {
+            UnaryOperator::PreIncrement | UnaryOperator::PostIncrement => BinaryOperator::Add,
+            _ => BinaryOperator::Subtract,
+        };
+        let one = match old {
+            DynamicValue::Byte(_) => DynamicValue::Byte(1),
+            _ => DynamicValue::Int(1),
+        };
+        if !matches!(old, DynamicValue::Int(_) | DynamicValue::Float(_) | DynamicValue::Byte(_)) {
+            return Err(invalid_operation(&format!("Cannot increment or decrement {:?}", old.get_type())));
+        }
+        let new = self.write_place(name, &keys, binary_operation(&old, &step, &one)?)?;
+        match operator {
+            UnaryOperator::PreIncrement | UnaryOperator::PreDecrement => Ok(new),
+            _ => Ok(old),
+        }
+    }
+
+    // Limits
+
+    // Counts one operation; the clock is only read every so often since it is comparatively slow
+    fn tick(&mut self) -> Result<(), RuntimeError> {
+        self.operations += 1;
+        if let Some(limit) = self.limits.max_operations {
+            if self.operations > limit {
+                return Err(RuntimeError::new(RuntimeErrorKind::OperationLimitExceeded(limit)));
+            }
+        }
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.limits.timeout) {
+            if self.operations.is_multiple_of(256) && Instant::now() >= deadline {
+                return Err(RuntimeError::new(RuntimeErrorKind::Timeout(timeout)));
+            }
+        }
+        Ok(())
+    }
+
+    // Charges every string, array or object a node produces against the memory budget
+    fn charge(&mut self, value: &DynamicValue) -> Result<(), RuntimeError> {
+        if let Some(limit) = self.limits.max_memory {
+            self.memory = self.memory.saturating_add(heap_size(value));
+            if self.memory > limit {
+                return Err(RuntimeError::new(RuntimeErrorKind::MemoryLimitExceeded(limit)));
+            }
+        }
+        Ok(())
+    }
+
+    // Enters a statement or expression, which costs stack in this interpreter whether or not it calls a function
+    fn nest(&mut self) -> Result<(), RuntimeError> {
+        if let Some(limit) = self.limits.max_nesting {
+            if self.depth >= limit {
+                return Err(RuntimeError::new(RuntimeErrorKind::NestingLimitExceeded(limit)));
+            }
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Rejects a value that would nest deeper than the limit once stored `base` levels down,
+    // so cloning, dropping and printing it cannot overflow the stack
+    fn check_nesting(&self, value: DynamicValue, base: usize) -> Result<DynamicValue, RuntimeError> {
+        if let Some(limit) = self.limits.max_nesting {
+            if nests_deeper(&value, limit.saturating_sub(base)) {
+                return Err(RuntimeError::new(RuntimeErrorKind::NestingLimitExceeded(limit)));
+            }
+        }
+        Ok(value)
+    }
+
+    // Functions
+
+    // Calls a variable holding a function, else a script function, else a host function.
+    // `names` holds the name of each argument, or None for positional ones
+    fn call_function(
+        &mut self,
+        name: &str,
+        arguments: Vec<DynamicValue>,
+        names: &[Option<&str>],
+        call_site: Span,
+    ) -> Result<DynamicValue, RuntimeError> {
+        let variable = self.lookup(name).ok().map(|variable| variable.value.clone());
+        if let Some(DynamicValue::Function(closure)) = &variable {
+            return self.call_script(&closure.function, Some(&closure.captured), arguments, names, call_site);
+        }
+        match (self.functions.get(name), self.natives.get(name)) {
+            (Some(function), _) => {
+                let function = Arc::clone(function);
+                self.call_script(&function, None, arguments, names, call_site)
+            },
+            (None, Some(native)) => {
+                // Host functions only take positional arguments
+                if let Some(named) = names.iter().flatten().next() {
+                    return Err(RuntimeError::new(RuntimeErrorKind::UnknownParameter {
+                        function: name.to_string(),
+                        name: named.to_string(),
+                    }));
+                }
+                let native = Arc::clone(native);
+                for capability in self.requirements.get(name).into_iter().flatten() {
+                    self.check_capability(name, capability)?;
+                }
+                self.enter_call(name, call_site)?;
+                let result = native(&arguments).and_then(|value| self.check_nesting(value, 0));
+                self.leave_call(result)
+            },
+            (None, None) if variable.is_some() => Err(invalid_operation(&format!("'{}' is not a function", name))),
+            (None, None) => Err(RuntimeError::new(RuntimeErrorKind::UndefinedFunction(name.to_string()))),
+        }
+    }
+
+    fn enter_call(&mut self, name: &str, call_site: Span) -> Result<(), RuntimeError> {
+        if let Some(limit) = self.limits.max_call_depth {
+            if self.call_stack.len() >= limit {
+                return Err(RuntimeError::new(RuntimeErrorKind::CallDepthExceeded(limit)));
+            }
+        }
+        self.call_stack.push(StackFrame { function: name.to_string(), call_site });
+        Ok(())
+    }
+
+    fn leave_call<T>(&mut self, result: Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let result = result.map_err(|error| self.traced(error));
+        self.call_stack.pop();
+        result
+    }
+
+    // Records the calls in progress in an error that has no backtrace yet, so it shows where the error was raised
+    fn traced(&self, mut error: RuntimeError) -> RuntimeError {
+        if error.backtrace.is_empty() {
+            error.backtrace = self.call_stack.iter().rev().cloned().collect();
+        }
+        error
+    }
+
+    // Runs a script function or lambda; a lambda's captured locals sit between the globals and its parameters
+    fn call_script(
+        &mut self,
+        function: &FunctionDefinition,
+        captured: Option<&HashMap<String, Variable>>,
+        arguments: Vec<DynamicValue>,
+        names: &[Option<&str>],
+        call_site: Span,
+    ) -> Result<DynamicValue, RuntimeError> {
+        let name = function.name.as_str();
+        let slots = function.signature.bind(name, names).map_err(RuntimeError::new)?;
+        self.enter_call(name, call_site)?;
+
+        // Functions see the globals and their own locals, never the caller's locals
+        let caller_scopes = self.scopes.split_off(1);
+        if let Some(captured) = captured {
+            self.scopes.push(captured.clone());
+        }
+        self.scopes.push(HashMap::new());
+        let result = self
+            .bind_parameters(function, &slots, arguments)
+            .and_then(|()| self.execute_statements(&function.body));
+        let result = self.leave_call(result);
+        self.scopes.truncate(1);
+        self.scopes.extend(caller_scopes);
+
+        // Falling off the end of a function returns Null
+        let value = match result? {
+            Flow::Return(value) => value,
+            _ => DynamicValue::Null,
+        };
+        match &function.signature.return_type {
+            Some(return_type) => coerce(value, return_type),
+            None => Ok(value),
+        }
+    }
+
+    // Declares each parameter in the new frame; defaults run there too, so they can use earlier parameters
+    fn bind_parameters(
+        &mut self,
+        function: &FunctionDefinition,
+        slots: &[Option<usize>],
+        arguments: Vec<DynamicValue>,
+    ) -> Result<(), RuntimeError> {
+        let mut arguments = arguments.into_iter().map(Some).collect::<Vec<_>>();
+        for (parameter, slot) in function.signature.parameters.iter().zip(slots) {
+            let value = match (slot, &parameter.default) {
+                (Some(index), _) => arguments[*index].take().unwrap_or(DynamicValue::Null),
+                (None, Some(default)) => self.evaluate(default)?,
+                (None, None) => DynamicValue::Null, // Ruled out by Signature::bind
+            };
+            let value = match &parameter.var_type {
+                Some(var_type) => coerce(value, var_type)?,
+                None => value,
+            };
+            let frame = self.scopes.last_mut().expect("call frame was just pushed");
+            frame.insert(parameter.name.clone(), Variable { var_type: parameter.var_type.clone(), value });
+        }
+        Ok(())
+    }
+
+    fn call_base_function(&mut self, function: &BaseFunctions, arguments: &[Spanned<Expression>]) -> Result<DynamicValue, RuntimeError> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+        match function {
+            BaseFunctions::WriteLn => {
+                self.check_capability("writeLn", Permissions::PRINT)?;
+                let line = arguments.iter().map(DynamicValue::to_string).collect::<Vec<_>>().join(" ");
+                (self.print_handler)(&line);
+                Ok(DynamicValue::Null)
+            },
+            other => Err(invalid_operation(&format!("{:?} cannot be called", other))),
+        }
+    }
+
+    fn check_capability(&self, function: &str, capability: &str) -> Result<(), RuntimeError> {
+        if self.permissions.allows(capability) {
+            Ok(())
+        } else {
+            Err(RuntimeError::new(RuntimeErrorKind::PermissionDenied {
+                function: function.to_string(),
+                capability: capability.to_string(),
+            }))
+        }
+    }
+
+    // Variables
+
+    fn declare(&mut self, name: &str, var_type: Option<VarType>, value: DynamicValue) -> Result<(), RuntimeError> {
+        let scope = self.scopes.last_mut().expect("global scope always exists");
+        if scope.contains_key(name) {
+            return Err(RuntimeError::new(RuntimeErrorKind::AlreadyDeclared(name.to_string())));
+        }
+        scope.insert(name.to_string(), Variable { var_type, value });
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<&Variable, RuntimeError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string())))
+    }
+
+    fn assign(&mut self, name: &str, value: DynamicValue) -> Result<DynamicValue, RuntimeError> {
+        let variable = self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string())))?;
+        let value = match &variable.var_type {
+            Some(var_type) => coerce(value, var_type)?,
+            None => value,
+        };
+        variable.value = value.clone();
+        Ok(value)
+    }
+
+    // Splits a place such as `order.items[2].qty` into its variable and the keys below it, evaluating indexes left to right
+    fn place_path<'a>(&mut self, expression: &'a Expression, keys: &mut Vec<DynamicValue>) -> Result<&'a str, RuntimeError> {
+        match expression {
+            Expression::Identifier(name) => Ok(name),
+            Expression::Index { target, index } => {
+                let name = self.place_path(&target.node, keys)?;
+                keys.push(self.evaluate(index)?);
+                Ok(name)
+            },
+            Expression::Member { target, name: member } => {
+                let name = self.place_path(&target.node, keys)?;
+                keys.push(DynamicValue::String(member.clone()));
+                Ok(name)
+            },
+            _ => Err(invalid_operation("Expected a variable")),
+        }
+    }
+
+    fn read_place(&self, name: &str, keys: &[DynamicValue]) -> Result<DynamicValue, RuntimeError> {
+        let mut value = Cow::Borrowed(&self.lookup(name)?.value);
+        for key in keys {
+            value = match value {
+                Cow::Borrowed(value) => element(value, key)?,
+                Cow::Owned(value) => Cow::Owned(element(&value, key)?.into_owned()),
+            };
+        }
+        Ok(value.into_owned())
+    }
+
+    // Writing a missing object key adds it; every other key on the path must already exist.
+    // Values stored into a typed collection must fit its element type
+    fn write_place(&mut self, name: &str, keys: &[DynamicValue], value: DynamicValue) -> Result<DynamicValue, RuntimeError> {
+        let Some((last, path)) = keys.split_last() else {
+            return self.assign(name, value);
+        };
+        let variable = self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::UndefinedVariable(name.to_string())))?;
+        let mut slot = &mut variable.value;
+        let mut slot_type = variable.var_type.as_ref();
+        for key in path {
+            slot = element_mut(slot, key, false)?;
+            slot_type = slot_type.and_then(VarType::element_type);
+        }
+        let value = match slot_type.and_then(VarType::element_type) {
+            Some(element) => coerce(value, element)?,
+            None => value,
+        };
+        if let Some(limit) = self.limits.max_nesting {
+            if nests_deeper(&value, limit.saturating_sub(keys.len())) {
+                return Err(RuntimeError::new(RuntimeErrorKind::NestingLimitExceeded(limit)));
+            }
+        }
+        *element_mut(slot, last, true)? = value.clone();
+        Ok(value)
+    }
+}
+
+// Value helpers
+
+// Reads `target[key]` or `target.key`. Arrays and strings also have a `length`, and a missing object key reads as null
+fn element<'a>(target: &'a DynamicValue, key: &DynamicValue) -> Result<Cow<'a, DynamicValue>, RuntimeError> {
+    match (target, key) {
+        (DynamicValue::Array(values), DynamicValue::String(name)) if name == "length" => Ok(Cow::Owned(DynamicValue::Int(values.len() as i64))),
+        (DynamicValue::String(string), DynamicValue::String(name)) if name == "length" => {
+            Ok(Cow::Owned(DynamicValue::Int(string.chars().count() as i64)))
+        },
+        (DynamicValue::Array(values), key) => Ok(Cow::Borrowed(&values[array_index(VarType::Array(None), key, values.len())?])),
+        (DynamicValue::String(string), key) => {
+            let index = array_index(VarType::String, key, string.chars().count())?;
+            Ok(Cow::Owned(DynamicValue::Char(string.chars().nth(index).expect("index is in bounds"))))
+        },
+        (DynamicValue::Object(values), DynamicValue::String(key)) => Ok(values.get(key).map_or(Cow::Owned(DynamicValue::Null), Cow::Borrowed)),
+        (DynamicValue::Object(_), key) => Err(type_mismatch(VarType::String, key)),
+        (DynamicValue::Error(error), DynamicValue::String(name)) => error_member(error, name).map(Cow::Owned),
+        (other, _) => Err(invalid_operation(&format!("Cannot index {:?}", other.get_type()))),
+    }
+}
+
+// What a script can read from a caught error
+fn error_member(error: &RuntimeError, name: &str) -> Result<DynamicValue, RuntimeError> {
+    match name {
+        "message" => Ok(DynamicValue::String(error.kind.to_string())),
+        "kind" => Ok(DynamicValue::String(error.kind.name().to_string())),
+        "line" => Ok(error.span.map_or(DynamicValue::Null, |span| DynamicValue::Int(span.line as i64))),
+        "column" => Ok(error.span.map_or(DynamicValue::Null, |span| DynamicValue::Int(span.column as i64))),
+        "stack" => Ok(DynamicValue::Array(error.backtrace.iter().map(|frame| DynamicValue::String(frame.to_string())).collect())),
+        _ => Err(invalid_operation(&format!("Error has no member '{}'", name))),
+    }
+}
+
+fn element_mut<'a>(target: &'a mut DynamicValue, key: &DynamicValue, insert: bool) -> Result<&'a mut DynamicValue, RuntimeError> {
+    match target {
+        DynamicValue::Array(values) => {
+            let index = array_index(VarType::Array(None), key, values.len())?;
+            Ok(&mut values[index])
+        },
+        DynamicValue::Object(values) => match key {
+            DynamicValue::String(key) if insert => Ok(values.entry(key.clone()).or_insert(DynamicValue::Null)),
+            DynamicValue::String(key) => values
+                .get_mut(key)
+                .ok_or_else(|| invalid_operation(&format!("Cannot assign below missing key '{}'", key))),
+            other => Err(type_mismatch(VarType::String, other)),
+        },
+        other => Err(invalid_operation(&format!("Cannot assign to an element of {:?}", other.get_type()))),
+    }
+}
+
+// Indexes count from zero and must be below the length
+fn array_index(target: VarType, key: &DynamicValue, length: usize) -> Result<usize, RuntimeError> {
+    let index = match key {
+        DynamicValue::Int(index) => *index,
+        DynamicValue::Byte(index) => *index as i64,
+        DynamicValue::String(name) => {
+            return Err(invalid_operation(&format!("{:?} has no member '{}'", target, name)));
+        },
+        other => return Err(type_mismatch(VarType::Int, other)),
+    };
+    usize::try_from(index)
+        .ok()
+        .filter(|index| *index < length)
+        .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds { index, length }))
+}
+
+pub(crate) fn compound_operator(operator: &AssignmentOperator) -> Option<BinaryOperator> {
+    match operator {
+        AssignmentOperator::Assign => None,
+        AssignmentOperator::AddAssign => Some(BinaryOperator::Add),
+        AssignmentOperator::SubtractAssign => Some(BinaryOperator::Subtract),
+        AssignmentOperator::MultiplyAssign => Some(BinaryOperator::Multiply),
+        AssignmentOperator::DivideAssign => Some(BinaryOperator::Divide),
+    }
+}
+
+pub(crate) fn type_mismatch(expected: VarType, found: &DynamicValue) -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::TypeMismatch { expected, found: found.get_type() })
+}
+
+fn invalid_operation(message: &str) -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::InvalidOperation(message.to_string()))
+}
+
+fn overflow() -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::Overflow)
+}
+
+fn default_value(var_type: &VarType) -> DynamicValue {
+    match var_type {
+        VarType::Int => DynamicValue::Int(0),
+        VarType::Float => DynamicValue::Float(0.0),
+        VarType::String => DynamicValue::String(String::new()),
+        VarType::Bool => DynamicValue::Bool(false),
+        VarType::Char => DynamicValue::Char('\0'),
+        VarType::Byte => DynamicValue::Byte(0),
+        VarType::Array(_) => DynamicValue::Array(Vec::new()),
+        VarType::Object(_) => DynamicValue::Object(HashMap::new()),
+        VarType::Node | VarType::Function | VarType::Error | VarType::Null => DynamicValue::Null,
+    }
+}
+
+// Fits a value into a declared type, allowing lossless widening and range-checked byte narrowing.
+// Typed collections fit each element the same way
+pub(crate) fn coerce(value: DynamicValue, var_type: &VarType) -> Result<DynamicValue, RuntimeError> {
+    match (value, var_type) {
+        (DynamicValue::Null, _) => Ok(DynamicValue::Null),
+        (DynamicValue::Array(values), VarType::Array(Some(element))) => {
+            let values = values.into_iter().map(|value| coerce(value, element)).collect::<Result<Vec<_>, _>>()?;
+            Ok(DynamicValue::Array(values))
+        },
+        (DynamicValue::Object(values), VarType::Object(Some(element))) => {
+            let values = values
+                .into_iter()
+                .map(|(key, value)| coerce(value, element).map(|value| (key, value)))
+                .collect::<Result<HashMap<_, _>, _>>()?;
+            Ok(DynamicValue::Object(values))
+        },
+        (DynamicValue::Int(value), VarType::Float) => Ok(DynamicValue::Float(value as f64)),
+        (DynamicValue::Byte(value), VarType::Int) => Ok(DynamicValue::Int(value as i64)),
+        (DynamicValue::Byte(value), VarType::Float) => Ok(DynamicValue::Float(value as f64)),
+        (DynamicValue::Int(value), VarType::Byte) => u8::try_from(value).map(DynamicValue::Byte).map_err(|_| overflow()),
+        (value, var_type) if value.get_type() == *var_type => Ok(value),
+        (value, var_type) => Err(type_mismatch(var_type.clone(), &value)),
+    }
+}
+
+fn as_float(value: &DynamicValue) -> Option<f64> {
+    match value {
+        DynamicValue::Int(value) => Some(*value as f64),
+        DynamicValue::Float(value) => Some(*value),
+        DynamicValue::Byte(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+fn as_integer(value: &DynamicValue) -> Option<i64> {
+    match value {
+        DynamicValue::Int(value) => Some(*value),
+        DynamicValue::Byte(value) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+fn values_equal(left: &DynamicValue, right: &DynamicValue) -> bool {
+    match (left, right) {
+        (DynamicValue::String(a), DynamicValue::String(b)) => a == b,
+        (DynamicValue::Bool(a), DynamicValue::Bool(b)) => a == b,
+        (DynamicValue::Char(a), DynamicValue::Char(b)) => a == b,
+        (DynamicValue::Null, DynamicValue::Null) => true,
+        (DynamicValue::Function(_), DynamicValue::Function(_)) => left == right,
+        (DynamicValue::Array(a), DynamicValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(a, b))
+        },
+        (DynamicValue::Object(a), DynamicValue::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| values_equal(a, b)))
+        },
+        _ => match (as_integer(left), as_integer(right)) {
+            (Some(a), Some(b)) => a == b,
+            _ => matches!((as_float(left), as_float(right)), (Some(a), Some(b)) if a == b),
+        },
+    }
+}
+
+// Whether an inclusive case range holds the subject; subjects of any other type never match
+fn in_range(subject: &DynamicValue, low: &DynamicValue, high: &DynamicValue) -> Result<bool, RuntimeError> {
+    if let (DynamicValue::Char(low), DynamicValue::Char(high)) = (low, high) {
+        return Ok(matches!(subject, DynamicValue::Char(ch) if low <= ch && ch <= high));
+    }
+    let (Some(low_float), Some(high_float)) = (as_float(low), as_float(high)) else {
+        return Err(invalid_operation("Case range bounds must both be numbers or both be chars"));
+    };
+    Ok(match (as_integer(subject), as_integer(low), as_integer(high)) {
+        (Some(value), Some(low), Some(high)) => low <= value && value <= high,
+        _ => as_float(subject).is_some_and(|value| low_float <= value && value <= high_float),
+    })
+}
+
+fn unary_operation(operator: &UnaryOperator, value: DynamicValue) -> Result<DynamicValue, RuntimeError> {
+    match (operator, value) {
+        (UnaryOperator::Not, DynamicValue::Bool(value)) => Ok(DynamicValue::Bool(!value)),
+        (UnaryOperator::Not, other) => Err(type_mismatch(VarType::Bool, &other)),
+        (UnaryOperator::Negate, DynamicValue::Int(value)) => value.checked_neg().map(DynamicValue::Int).ok_or_else(overflow),
+        (UnaryOperator::Negate, DynamicValue::Float(value)) => Ok(DynamicValue::Float(-value)),
+        (UnaryOperator::Negate, DynamicValue::Byte(value)) => Ok(DynamicValue::Int(-(value as i64))),
+        (UnaryOperator::Negate, other) => Err(invalid_operation(&format!("Cannot negate {:?}", other.get_type()))),
+        (_, value @ (DynamicValue::Int(_) | DynamicValue::Float(_) | DynamicValue::Byte(_))) => Ok(value),
+        (_, other) => Err(invalid_operation(&format!("Cannot apply unary '+' to {:?}", other.get_type()))),
+    }
+}
+
+fn binary_operation(left: &DynamicValue, operator: &BinaryOperator, right: &DynamicValue) -> Result<DynamicValue, RuntimeError> {
+    let unsupported = || invalid_operation(&format!(
+        "Cannot apply {:?} to {:?} and {:?}", operator, left.get_type(), right.get_type()
+    ));
+
+    match operator {
+        BinaryOperator::Equal => Ok(DynamicValue::Bool(values_equal(left, right))),
+        BinaryOperator::NotEqual => Ok(DynamicValue::Bool(!values_equal(left, right))),
+        BinaryOperator::Less | BinaryOperator::LessEqual | BinaryOperator::Greater | BinaryOperator::GreaterEqual => {
+            let ordering = match (left, right) {
+                (DynamicValue::String(a), DynamicValue::String(b)) => a.partial_cmp(b),
+                (DynamicValue::Char(a), DynamicValue::Char(b)) => a.partial_cmp(b),
+                _ => match (as_integer(left), as_integer(right)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => match (as_float(left), as_float(right)) {
+                        (Some(a), Some(b)) => a.partial_cmp(&b),
+                        _ => return Err(unsupported()),
+                    },
+                },
+            };
+            let result = match ordering {
+                Some(ordering) => match operator {
+                    BinaryOperator::Less => ordering.is_lt(),
+                    BinaryOperator::LessEqual => ordering.is_le(),
+                    BinaryOperator::Greater => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                None => false, // NaN compares false to everything
+            };
+            Ok(DynamicValue::Bool(result))
+        },
+        BinaryOperator::And | BinaryOperator::Or => match (left, right) {
+            (DynamicValue::Bool(a), DynamicValue::Bool(b)) => {
+                Ok(DynamicValue::Bool(if *operator == BinaryOperator::And { *a && *b } else { *a || *b }))
+            },
+            _ => Err(unsupported()),
+        },
+        BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
+            let apply = |a: i64, b: i64| match operator {
+                BinaryOperator::BitAnd => a & b,
+                BinaryOperator::BitOr => a | b,
+                _ => a ^ b,
+            };
+            match (left, right) {
+                (DynamicValue::Bool(a), DynamicValue::Bool(b)) => Ok(DynamicValue::Bool(apply(*a as i64, *b as i64) != 0)),
+                (DynamicValue::Byte(a), DynamicValue::Byte(b)) => Ok(DynamicValue::Byte(apply(*a as i64, *b as i64) as u8)),
+                _ => match (as_integer(left), as_integer(right)) {
+                    (Some(a), Some(b)) => Ok(DynamicValue::Int(apply(a, b))),
+                    _ => Err(unsupported()),
+                },
+            }
+        },
+        BinaryOperator::Add if matches!(left, DynamicValue::String(_)) || matches!(right, DynamicValue::String(_)) => {
+            Ok(DynamicValue::String(format!("{}{}", left, right)))
+        },
+        BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => {
+            match (left, right) {
+                (DynamicValue::Array(a), DynamicValue::Array(b)) if *operator == BinaryOperator::Add => {
+                    Ok(DynamicValue::Array(a.iter().chain(b).cloned().collect()))
+                },
+                (DynamicValue::Byte(a), DynamicValue::Byte(b)) => {
+                    let result = integer_arithmetic(*a as i64, operator, *b as i64)?;
+                    u8::try_from(result).map(DynamicValue::Byte).map_err(|_| overflow())
+                },
+                _ => match (as_integer(left), as_integer(right)) {
+                    (Some(a), Some(b)) => integer_arithmetic(a, operator, b).map(DynamicValue::Int),
+                    _ => match (as_float(left), as_float(right)) {
+                        (Some(a), Some(b)) => Ok(DynamicValue::Float(float_arithmetic(a, operator, b))),
+                        _ => Err(unsupported()),
+                    },
+                },
+            }
+        },
+    }
+}
+
+fn integer_arithmetic(a: i64, operator: &BinaryOperator, b: i64) -> Result<i64, RuntimeError> {
+    if b == 0 && matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo) {
+        return Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero));
+    }
+    let result = match operator {
+        BinaryOperator::Add => a.checked_add(b),
+        BinaryOperator::Subtract => a.checked_sub(b),
+        BinaryOperator::Multiply => a.checked_mul(b),
+        BinaryOperator::Divide => a.checked_div(b),
+        _ => a.checked_rem(b),
+    };
+    result.ok_or_else(overflow)
+}
+
+fn float_arithmetic(a: f64, operator: &BinaryOperator, b: f64) -> f64 {
+    match operator {
+        BinaryOperator::Add => a + b,
+        BinaryOperator::Subtract => a - b,
+        BinaryOperator::Multiply => a * b,
+        BinaryOperator::Divide => a / b,
+        _ => a % b,
+    }
+}
+
+// Applies an interpolation format such as {price:.2}, {name:>8} or {flags:04x}
+fn format_with_spec(value: &DynamicValue, spec: &FormatSpec) -> Result<String, RuntimeError> {
+    let unsupported = || invalid_operation(&format!("Format specifier does not apply to {:?}", value.get_type()));
+    let numeric = as_float(value).is_some();
+
+    let body = match (spec.radix, spec.precision) {
+        (Some(_), Some(_)) => return Err(unsupported()),
+        (Some(radix), None) => {
+            let integer = as_integer(value).ok_or_else(unsupported)?;
+            match radix {
+                'x' => format!("{:x}", integer),
+                'X' => format!("{:X}", integer),
+                'o' => format!("{:o}", integer),
+                _ => format!("{:b}", integer),
+            }
+        },
+        (None, Some(precision)) => match value {
+            DynamicValue::String(string) => string.chars().take(precision).collect(),
+            _ => format!("{:.*}", precision, as_float(value).ok_or_else(unsupported)?),
+        },
+        (None, None) => value.to_string(),
+    };
+
+    let length = body.chars().count();
+    let padding = spec.width.unwrap_or(0).saturating_sub(length);
+    if spec.zero_pad {
+        if !numeric {
+            return Err(unsupported());
+        }
+        // Zeros go between the sign and the digits
+        let (sign, digits) = body.split_at(if body.starts_with('-') { 1 } else { 0 });
+        return Ok(format!("{}{}{}", sign, "0".repeat(padding), digits));
+    }
+    Ok(match spec.align.unwrap_or(if numeric { '>' } else { '<' }) {
+        '>' => format!("{}{}", " ".repeat(padding), body),
+        '^' => format!("{}{}{}", " ".repeat(padding / 2), body, " ".repeat(padding - padding / 2)),
+        _ => format!("{}{}", body, " ".repeat(padding)),
+    })
+}
+
+// Whether a value has more than `limit` levels of arrays, objects and closures, walked without recursing
+fn nests_deeper(value: &DynamicValue, limit: usize) -> bool {
+    let mut pending = vec![(value, 1)];
+    while let Some((value, depth)) = pending.pop() {
+        if depth > limit && matches!(value, DynamicValue::Array(_) | DynamicValue::Object(_) | DynamicValue::Function(_)) {
+            return true;
+        }
+        match value {
+            DynamicValue::Array(values) => pending.extend(values.iter().map(|value| (value, depth + 1))),
+            DynamicValue::Object(values) => pending.extend(values.values().map(|value| (value, depth + 1))),
+            DynamicValue::Function(closure) => pending.extend(closure.captured.values().map(|variable| (&variable.value, depth + 1))),
+            _ => {},
+        }
+    }
+    false
+}
+
+fn heap_size(value: &DynamicValue) -> usize {
+    match value {
+        DynamicValue::String(string) => string.len(),
+        DynamicValue::Array(values) => values.iter().map(|value| std::mem::size_of::<DynamicValue>() + heap_size(value)).sum(),
+        DynamicValue::Object(values) => {
+            values.iter().map(|(key, value)| key.len() + std::mem::size_of::<DynamicValue>() + heap_size(value)).sum()
+        },
+        DynamicValue::Function(closure) => closure
+            .captured
+            .iter()
+            .map(|(name, variable)| name.len() + std::mem::size_of::<DynamicValue>() + heap_size(&variable.value))
+            .sum(),
+        _ => 0,
+    }
+}
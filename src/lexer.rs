@@ -1,11 +1,23 @@
+use unicode_xid::UnicodeXID;
 use crate::types::var_type::VarType;
 use crate::types::tokens::Tokens;
 use crate::types::numeric::Numeric;
 use crate::types::base_functions::BaseFunctions;
+use crate::types::lex_error::LexError;
+use crate::types::lex_error_kind::LexErrorKind;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::interpolation_part::InterpolationPart;
 
 pub struct Lexer {
     input: String,
     pos: usize,
+    line: usize,        // Line reached by the last computed span
+    line_start: usize,  // Byte offset where that line starts
+    scanned: usize,     // Byte offset up to which newlines have been counted
+    nested_errors: Vec<LexError>, // Errors from expressions inside interpolated strings
+    offset: usize,      // Byte offset of the input in the script, for interpolation holes
+    column_offset: usize, // Columns before the input on its first line
 }
 
 impl Lexer {
@@ -13,220 +25,538 @@ impl Lexer {
         Lexer {
             input: script.to_string(),
             pos: 0,
+            line: 1,
+            line_start: 0,
+            scanned: 0,
+            nested_errors: Vec::new(),
+            offset: 0,
+            column_offset: 0,
         }
     }
 
-    pub fn tokenize(&mut self) -> Vec<Tokens> {
+    // Lexes the whole input, reporting every malformed token rather than stopping at the first
+    pub fn tokenize(&mut self) -> Result<Vec<Spanned<Tokens>>, Vec<LexError>> {
         let mut tokens = Vec::new();
-        while self.pos < self.input.len() {
+        let mut errors = Vec::new();
+        loop {
             self.skip_whitespace();
-            if let Some(token) = self.next_token() {
-                tokens.push(token);
+            if self.pos >= self.input.len() {
+                break;
+            }
+            let start = self.pos;
+            let result = match self.input.get(self.pos..self.pos + 2) {
+                Some("//") | Some("/*") => match self.tokenize_comment() {
+                    Ok(Some(token)) => Ok(token),
+                    Ok(None) => continue, // Plain comments produce no token
+                    Err(kind) => Err(kind),
+                },
+                _ => self.next_token(),
+            };
+            let span = self.span_from(start);
+            match result {
+                Ok(token) => tokens.push(Spanned::new(token, span)),
+                Err(kind) => errors.push(LexError::new(kind, span)),
             }
+            errors.append(&mut self.nested_errors);
+        }
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors)
         }
-        tokens
     }
 
-    fn next_token(&mut self) -> Option<Tokens> {
+    // Line and line start of a byte offset at or after the last computed span, without caching it
+    fn location_at(&self, pos: usize) -> (usize, usize) {
+        let mut line = self.line;
+        let mut line_start = self.line_start;
+        for (offset, ch) in self.input[self.scanned..pos].char_indices() {
+            if ch == '\n' {
+                line += 1;
+                line_start = self.scanned + offset + 1;
+            }
+        }
+        (line, line_start)
+    }
+
+    // Builds the span from start to the current position, counting lines incrementally
+    fn span_from(&mut self, start: usize) -> Span {
+        for (offset, ch) in self.input[self.scanned..start].char_indices() {
+            if ch == '\n' {
+                self.line += 1;
+                self.line_start = self.scanned + offset + 1;
+            }
+        }
+        self.scanned = start;
+        let column = self.column_at(self.line_start, start);
+        Span::new(self.offset + start, self.offset + self.pos.min(self.input.len()), self.line, column)
+    }
+
+    // Column of a byte offset on the line starting at line_start
+    fn column_at(&self, line_start: usize, pos: usize) -> usize {
+        let before = if line_start == 0 { self.column_offset } else { 0 };
+        before + self.input[line_start..pos].chars().count() + 1
+    }
+
+    fn next_token(&mut self) -> Result<Tokens, LexErrorKind> {
         let input_slice = &self.input[self.pos..];
-        let ch = input_slice.chars().next()?;
+        let ch = match input_slice.chars().next() {
+            Some(ch) => ch,
+            None => return Err(LexErrorKind::UnknownCharacter('\0')),
+        };
 
         match ch {
-            ':' => { self.pos += 1; Some(Tokens::Colon) },
-            ';' => { self.pos += 1; Some(Tokens::SemiColon) },
-            '.' => { self.pos += 1; Some(Tokens::Period) },
+            ':' => { self.pos += 1; Ok(Tokens::Colon) },
+            ';' => { self.pos += 1; Ok(Tokens::SemiColon) },
+            '.' => { self.pos += 1; Ok(Tokens::Period) },
             '=' => {
                 match input_slice.get(0..2) {
-                    Some("==") => { self.pos += 2; Some(Tokens::EqualEqual) },
-                    Some("=+") => { self.pos += 2; Some(Tokens::EqualPlus) },
-                    Some("=-") => { self.pos += 2; Some(Tokens::EqualMinus) },
-                    Some("=*") => { self.pos += 2; Some(Tokens::EqualStar) },
-                    Some("=/") => { self.pos += 2; Some(Tokens::EqualForwardSlash) },
-                    _ => { self.pos += 1; Some(Tokens::Equals) }
+                    Some("==") => { self.pos += 2; Ok(Tokens::EqualEqual) },
+                    Some("=>") => { self.pos += 2; Ok(Tokens::FatArrow) },
+                    _ => { self.pos += 1; Ok(Tokens::Equals) }
                 }
             },
             '+' => {
                 match input_slice.get(0..2) {
-                    Some("+=") => { self.pos += 2; Some(Tokens::PlusEqual) },
-                    Some("++") => { self.pos += 2; Some(Tokens::PlusPlus) },
-                    _ => { self.pos += 1; Some(Tokens::Plus) }
+                    Some("+=") => { self.pos += 2; Ok(Tokens::PlusEqual) },
+                    Some("++") => { self.pos += 2; Ok(Tokens::PlusPlus) },
+                    _ => { self.pos += 1; Ok(Tokens::Plus) }
                 }
             },
             '-' => {
                 match input_slice.get(0..2) {
-                    Some("-=") => { self.pos += 2; Some(Tokens::MinusEqual) },
-                    Some("--") => { self.pos += 2; Some(Tokens::MinusMinus) },
-                    _ => { self.pos += 1; Some(Tokens::Minus) }
+                    Some("-=") => { self.pos += 2; Ok(Tokens::MinusEqual) },
+                    Some("--") => { self.pos += 2; Ok(Tokens::MinusMinus) },
+                    Some("->") => { self.pos += 2; Ok(Tokens::Arrow) },
+                    _ => { self.pos += 1; Ok(Tokens::Minus) }
                 }
             },
             '*' => {
                 match input_slice.get(0..2) {
-                    Some("*=") => { self.pos += 2; Some(Tokens::EqualStar) },
-                    _ => { self.pos += 1; Some(Tokens::Star) }
+                    Some("*=") => { self.pos += 2; Ok(Tokens::StarEqual) },
+                    _ => { self.pos += 1; Ok(Tokens::Star) }
                 }
             },
             '/' => {
                 match input_slice.get(0..2) {
-                    Some("/=") => { self.pos += 2; Some(Tokens::EqualForwardSlash) },
-                    _ => { self.pos += 1; Some(Tokens::ForwardSlash) }
+                    Some("/=") => { self.pos += 2; Ok(Tokens::ForwardSlashEqual) },
+                    _ => { self.pos += 1; Ok(Tokens::ForwardSlash) }
                 }
             },
-            '%' => { self.pos += 1; Some(Tokens::Percent) },
-            '\\' => { self.pos += 1; Some(Tokens::BackSlash) },
+            '%' => { self.pos += 1; Ok(Tokens::Percent) },
+            '\\' => { self.pos += 1; Ok(Tokens::BackSlash) },
             '!' => {
                 match input_slice.get(0..2) {
-                    Some("!=") => { self.pos += 2; Some(Tokens::NotEqual) },
-                    _ => { self.pos += 1; Some(Tokens::Bang) }
+                    Some("!=") => { self.pos += 2; Ok(Tokens::NotEqual) },
+                    _ => { self.pos += 1; Ok(Tokens::Bang) }
                 }
             },
             '>' => {
                 match input_slice.get(0..2) {
-                    Some(">=") => { self.pos += 2; Some(Tokens::GreaterEqual) },
-                    _ => { self.pos += 1; Some(Tokens::Greater) }
+                    Some(">=") => { self.pos += 2; Ok(Tokens::GreaterEqual) },
+                    _ => { self.pos += 1; Ok(Tokens::Greater) }
                 }
             },
             '<' => {
                 match input_slice.get(0..2) {
-                    Some("<=") => { self.pos += 2; Some(Tokens::LessEqual) },
-                    _ => { self.pos += 1; Some(Tokens::Less) }
+                    Some("<=") => { self.pos += 2; Ok(Tokens::LessEqual) },
+                    _ => { self.pos += 1; Ok(Tokens::Less) }
                 }
             },
             '&' => {
                 match input_slice.get(0..2) {
-                    Some("&&") => { self.pos += 2; Some(Tokens::AndAnd) },
-                    _ => { self.pos += 1; Some(Tokens::Ampersand) }
+                    Some("&&") => { self.pos += 2; Ok(Tokens::AndAnd) },
+                    _ => { self.pos += 1; Ok(Tokens::Ampersand) }
                 }
             },
             '|' => {
                 match input_slice.get(0..2) {
-                    Some("||") => { self.pos += 2; Some(Tokens::Or) },
-                    _ => { self.pos += 1; Some(Tokens::Pipe) }
+                    Some("||") => { self.pos += 2; Ok(Tokens::Or) },
+                    _ => { self.pos += 1; Ok(Tokens::Pipe) }
+                }
+            },
+            '(' => { self.pos += 1; Ok(Tokens::LParentheses) },
+            ')' => { self.pos += 1; Ok(Tokens::RParentheses) },
+            '{' => { self.pos += 1; Ok(Tokens::LBrace) },
+            '}' => { self.pos += 1; Ok(Tokens::RBrace) },
+            '[' => { self.pos += 1; Ok(Tokens::LSquareBracket) },
+            ']' => { self.pos += 1; Ok(Tokens::RSquareBracket) },
+            ',' => { self.pos += 1; Ok(Tokens::Comma) },
+            '$' => {
+                match input_slice.get(0..2) {
+                    Some("$\"") => self.tokenize_interpolated_string(),
+                    _ => { self.pos += 1; Ok(Tokens::Dollor) }
                 }
             },
-            '(' => { self.pos += 1; Some(Tokens::LParentheses) },
-            ')' => { self.pos += 1; Some(Tokens::RParentheses) },
-            '{' => { self.pos += 1; Some(Tokens::LBrace) },
-            '}' => { self.pos += 1; Some(Tokens::RBrace) },
-            '[' => { self.pos += 1; Some(Tokens::LSquareBracket) },
-            ']' => { self.pos += 1; Some(Tokens::RSquareBracket) },
-            ',' => { self.pos += 1; Some(Tokens::Comma) },
-            '$' => { self.pos += 1; Some(Tokens::Dollor) },
-            '@' => { self.pos += 1; Some(Tokens::At) },
-            '#' => { self.pos += 1; Some(Tokens::Hash) },
-            '^' => { self.pos += 1; Some(Tokens::Caret) },
+            '@' => { self.pos += 1; Ok(Tokens::At) },
+            '#' => { self.pos += 1; Ok(Tokens::Hash) },
+            '^' => { self.pos += 1; Ok(Tokens::Caret) },
             '"' => self.tokenize_string_literal(),
             '\'' => self.tokenize_char_literal(),
             '0'..='9' => self.tokenize_number_literal(),
-            'a'..='z' | 'A'..='Z' | '_' => self.tokenize_identifier_or_keyword(),
-            _ => { self.pos += 1; None }, // Skip unknown characters
+            _ if self.is_identifier_start(ch) => self.tokenize_identifier_or_keyword(),
+            _ => { self.pos += ch.len_utf8(); Err(LexErrorKind::UnknownCharacter(ch)) },
+        }
+    }
+
+    // Skips a comment, returning a token only for `///` doc comments
+    fn tokenize_comment(&mut self) -> Result<Option<Tokens>, LexErrorKind> {
+        if self.input[self.pos..].starts_with("/*") {
+            self.pos += 2; // Skip the opening /*
+            let mut depth = 1;
+            while depth > 0 {
+                let rest = &self.input[self.pos..];
+                if rest.starts_with("/*") {
+                    depth += 1;
+                    self.pos += 2;
+                } else if rest.starts_with("*/") {
+                    depth -= 1;
+                    self.pos += 2;
+                } else if let Some(ch) = rest.chars().next() {
+                    self.pos += ch.len_utf8();
+                } else {
+                    return Err(LexErrorKind::UnterminatedComment);
+                }
+            }
+            return Ok(None);
+        }
+
+        let is_doc = self.input[self.pos..].starts_with("///") && !self.input[self.pos..].starts_with("////");
+        let end = self.input[self.pos..].find('\n').map_or(self.input.len(), |offset| self.pos + offset);
+        let text = self.input[self.pos..end].trim_start_matches('/').trim().to_string();
+        self.pos = end;
+        if is_doc {
+            Ok(Some(Tokens::DocComment(text)))
+        } else {
+            Ok(None)
         }
     }
 
-    fn tokenize_string_literal(&mut self) -> Option<Tokens> {
+    fn tokenize_string_literal(&mut self) -> Result<Tokens, LexErrorKind> {
         self.pos += 1; // Skip the opening quote
         let mut literal = String::new();
-        while self.pos < self.input.len() {
-            let ch = self.input[self.pos..].chars().next()?;
-            if ch == '"' {
-                break; // Closing quote found
-            } else if ch == '\\' {
-                // Handle escape sequence
-                self.pos += 1; // Skip the backslash
-                if self.pos >= self.input.len() {
-                    break; // Unexpected end of input
-                }
-                let escape_ch = self.input[self.pos..].chars().next()?;
-                match escape_ch {
-                    'n' => literal.push('\n'),
-                    't' => literal.push('\t'),
-                    'r' => literal.push('\r'),
-                    '\\' => literal.push('\\'),
-                    '"' => literal.push('"'),
-                    '\'' => literal.push('\''),
-                    _ => literal.push(escape_ch), // Unrecognized escape, treat as literal
-                }
-            } else {
+        let mut error = None;
+        loop {
+            let ch = match self.input[self.pos..].chars().next() {
+                Some(ch) => ch,
+                None => return Err(LexErrorKind::UnterminatedString),
+            };
+            self.pos += ch.len_utf8();
+            match ch {
+                '"' => break, // Closing quote found
+                '\\' => match self.read_escape() {
+                    Ok(escaped) => literal.push(escaped),
+                    Err(kind) => { error.get_or_insert(kind); }, // Keep going so lexing resumes after the literal
+                },
+                _ => literal.push(ch),
+            }
+        }
+        match error {
+            Some(kind) => Err(kind),
+            None => Ok(Tokens::StringLiteral(literal)),
+        }
+    }
+
+    // Splits $"..." into literal text and holes; `{{` and `}}` stand for literal braces
+    fn tokenize_interpolated_string(&mut self) -> Result<Tokens, LexErrorKind> {
+        self.pos += 2; // Skip the $ and the opening quote
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut error = None;
+        loop {
+            let rest = &self.input[self.pos..];
+            let ch = match rest.chars().next() {
+                Some(ch) => ch,
+                None => return Err(LexErrorKind::UnterminatedString),
+            };
+            if rest.starts_with("{{") || rest.starts_with("}}") {
                 literal.push(ch);
+                self.pos += 2;
+                continue;
+            }
+            self.pos += ch.len_utf8();
+            match ch {
+                '"' => break, // Closing quote found
+                '\\' => match self.read_escape() {
+                    Ok(escaped) => literal.push(escaped),
+                    Err(kind) => { error.get_or_insert(kind); },
+                },
+                '{' => {
+                    if !literal.is_empty() {
+                        parts.push(InterpolationPart::Literal(std::mem::take(&mut literal)));
+                    }
+                    match self.tokenize_interpolation_hole()? {
+                        Ok(part) => parts.push(part),
+                        Err(kind) => { error.get_or_insert(kind); },
+                    }
+                },
+                '}' => { error.get_or_insert(LexErrorKind::InvalidInterpolation("unmatched '}'".to_string())); },
+                _ => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(InterpolationPart::Literal(literal));
+        }
+        match error {
+            Some(kind) => Err(kind),
+            None => Ok(Tokens::InterpolatedString(parts)),
+        }
+    }
+
+    // Scans one {expression:format} hole after its opening brace and lexes the expression.
+    // The outer error means the string itself cannot be delimited; the inner one is recoverable.
+    fn tokenize_interpolation_hole(&mut self) -> Result<Result<InterpolationPart, LexErrorKind>, LexErrorKind> {
+        let start = self.pos;
+        let mut depth = 0;
+        let mut format_start = None;
+        loop {
+            let ch = match self.input[self.pos..].chars().next() {
+                Some(ch) => ch,
+                None => return Err(LexErrorKind::UnterminatedString),
+            };
+            match ch {
+                '"' | '\'' => self.skip_quoted(ch)?,
+                '(' | '[' | '{' => { depth += 1; self.pos += 1; },
+                ')' | ']' => { depth -= 1; self.pos += 1; },
+                '}' if depth == 0 => break,
+                '}' => { depth -= 1; self.pos += 1; },
+                ':' if depth == 0 && format_start.is_none() => { format_start = Some(self.pos); self.pos += 1; },
+                _ => self.pos += ch.len_utf8(),
+            }
+        }
+        let end = self.pos;
+        self.pos += 1; // Skip the closing brace
+
+        let expression_end = format_start.unwrap_or(end);
+        if self.input[start..expression_end].trim().is_empty() {
+            return Ok(Err(LexErrorKind::InvalidInterpolation("empty expression".to_string())));
+        }
+        let format = format_start
+            .map(|colon| self.input[colon + 1..end].trim().to_string())
+            .filter(|format| !format.is_empty());
+
+        // Lex just the expression, offset so its tokens carry spans into the whole script
+        let (line, line_start) = self.location_at(start);
+        let mut lexer = Lexer {
+            input: self.input[start..expression_end].to_string(),
+            pos: 0,
+            line,
+            line_start: 0,
+            scanned: 0,
+            nested_errors: Vec::new(),
+            offset: self.offset + start,
+            column_offset: self.column_at(line_start, start) - 1,
+        };
+        match lexer.tokenize() {
+            Ok(tokens) => Ok(Ok(InterpolationPart::Hole { tokens, format })),
+            Err(mut errors) => {
+                self.nested_errors.append(&mut errors);
+                Ok(Ok(InterpolationPart::Hole { tokens: Vec::new(), format }))
+            },
+        }
+    }
+
+    // Skips a string or char literal nested inside an interpolation hole
+    fn skip_quoted(&mut self, quote: char) -> Result<(), LexErrorKind> {
+        self.pos += 1;
+        loop {
+            let ch = match self.input[self.pos..].chars().next() {
+                Some(ch) => ch,
+                None => return Err(LexErrorKind::UnterminatedString),
+            };
+            self.pos += ch.len_utf8();
+            if ch == quote {
+                return Ok(());
+            }
+            if ch == '\\' {
+                if let Some(escaped) = self.input[self.pos..].chars().next() {
+                    self.pos += escaped.len_utf8();
+                }
             }
-            self.pos += 1;
         }
-        self.pos += 1; // Skip the closing quote
-        Some(Tokens::StringLiteral(literal))
     }
 
-    fn tokenize_identifier_or_keyword(&mut self) -> Option<Tokens> {
+    fn tokenize_identifier_or_keyword(&mut self) -> Result<Tokens, LexErrorKind> {
         let start = self.pos;
-        while self.pos < self.input.len() && self.is_identifier_char(self.input[self.pos..].chars().next()?) {
-            self.pos += 1;
+        while let Some(ch) = self.input[self.pos..].chars().next() {
+            if !self.is_identifier_char(ch) {
+                break;
+            }
+            self.pos += ch.len_utf8();
         }
         let identifier = &self.input[start..self.pos];
         if let Some(builtin) = BaseFunctions::from_str(identifier) {
-            Some(Tokens::BaseFunctions(builtin))
-        } else if let Some(var_type) = VarType::from_str(identifier) {
-            Some(Tokens::BaseVariables(var_type))
+            Ok(Tokens::BaseFunctions(builtin))
+        } else if let Some(var_type) = VarType::from_str(identifier).filter(|_| !VarType::CONTEXTUAL.contains(&identifier)) {
+            Ok(Tokens::BaseVariables(var_type))
         } else if identifier == "true" {
-            Some(Tokens::BoolLiteral(true))
+            Ok(Tokens::BoolLiteral(true))
         } else if identifier == "false" {
-            Some(Tokens::BoolLiteral(false))
+            Ok(Tokens::BoolLiteral(false))
         } else {
-            Some(Tokens::Identifier(identifier.to_string()))
+            Ok(Tokens::Identifier(identifier.to_string()))
         }
     }
 
-    fn tokenize_number_literal(&mut self) -> Option<Tokens> {
+    fn tokenize_number_literal(&mut self) -> Result<Tokens, LexErrorKind> {
         let start = self.pos;
-        let mut has_decimal_point = false;
+        let radix = match &self.input.as_bytes()[self.pos..] {
+            [b'0', b'x' | b'X', ..] => 16,
+            [b'0', b'o' | b'O', ..] => 8,
+            [b'0', b'b' | b'B', b'0' | b'1' | b'_', ..] => 2, // A bare `0b` is a byte zero
+            _ => 10,
+        };
+        if radix != 10 {
+            self.pos += 2;
+        }
 
-        while self.pos < self.input.len() {
-            let ch = self.input[self.pos..].chars().next()?;
-            if ch.is_digit(10) {
-                self.pos += 1;
-            } else if ch == '.' && !has_decimal_point {
-                has_decimal_point = true;
-                self.pos += 1;
-            } else {
-                break;
+        // Take the whole literal, including suffix letters, so `12abc` is one bad number rather than two tokens
+        let mut has_decimal_point = false;
+        while let Some(&byte) = self.input.as_bytes().get(self.pos) {
+            let next_is_digit = self.input.as_bytes().get(self.pos + 1).is_some_and(u8::is_ascii_digit);
+            match byte {
+                b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.pos += 1,
+                b'.' if radix == 10 && !has_decimal_point && next_is_digit => {
+                    has_decimal_point = true;
+                    self.pos += 1;
+                },
+                b'+' | b'-' if radix == 10 && next_is_digit && matches!(self.input.as_bytes()[self.pos - 1], b'e' | b'E') => {
+                    self.pos += 1;
+                },
+                _ => break,
             }
         }
 
-        let number_str = &self.input[start..self.pos];
-        if has_decimal_point {
-            number_str.parse::<f64>().ok().map(|float_value| Tokens::NumberLiteral(Numeric::Float(float_value)))
+        let literal = &self.input[start..self.pos];
+        let digits = if radix == 10 { literal } else { &literal[2..] };
+        Self::parse_number(literal, digits, radix).map(Tokens::NumberLiteral)
+    }
+
+    // Splits off the type suffix and checks digit separators before converting
+    fn parse_number(literal: &str, digits: &str, radix: u32) -> Result<Numeric, LexErrorKind> {
+        let invalid = || LexErrorKind::InvalidNumber(literal.to_string());
+        let out_of_range = || LexErrorKind::NumberOutOfRange(literal.to_string());
+
+        // Hex digits include b and f, so hex literals cannot take a suffix
+        let (body, suffix) = if radix == 16 {
+            (digits, "")
         } else {
-            number_str.parse::<i64>().ok().map(|int_value| Tokens::NumberLiteral(Numeric::Int(int_value)))
+            let split = digits.trim_end_matches(|ch: char| ch.is_ascii_alphabetic()).len();
+            digits.split_at(split)
+        };
+        let (mantissa, exponent) = match body.find(['e', 'E']) {
+            Some(index) if radix == 10 => (&body[..index], Some(&body[index + 1..])),
+            _ => (body, None),
+        };
+        let (whole, fraction) = match mantissa.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (mantissa, None),
+        };
+
+        let whole = Self::strip_separators(whole, radix).ok_or_else(invalid)?;
+        let fraction = fraction.map(|fraction| Self::strip_separators(fraction, radix).ok_or_else(invalid)).transpose()?;
+        let exponent = exponent
+            .map(|exponent| {
+                let (sign, digits) = exponent.split_at(if exponent.starts_with(['+', '-']) { 1 } else { 0 });
+                Self::strip_separators(digits, radix).map(|digits| format!("{}{}", sign, digits)).ok_or_else(invalid)
+            })
+            .transpose()?;
+
+        let is_float = fraction.is_some() || exponent.is_some();
+        match suffix {
+            // 2^63 only fits once negated, so it lexes as i64::MIN and the parser insists on the minus
+            "" | "i" if !is_float => match u64::from_str_radix(&whole, radix) {
+                Ok(value) if value == 1 << 63 => Ok(Numeric::Int(i64::MIN)),
+                _ => i64::from_str_radix(&whole, radix).map(Numeric::Int).map_err(|_| out_of_range()),
+            },
+            "b" if !is_float => u8::from_str_radix(&whole, radix).map(Numeric::Byte).map_err(|_| out_of_range()),
+            "" | "f" if radix == 10 => {
+                let mut text = whole;
+                if let Some(fraction) = fraction {
+                    text = format!("{}.{}", text, fraction);
+                }
+                if let Some(exponent) = exponent {
+                    text = format!("{}e{}", text, exponent);
+                }
+                match text.parse::<f64>() {
+                    Ok(value) if value.is_finite() => Ok(Numeric::Float(value)),
+                    Ok(_) => Err(out_of_range()),
+                    Err(_) => Err(invalid()),
+                }
+            },
+            _ => Err(invalid()),
         }
     }
 
-    fn tokenize_char_literal(&mut self) -> Option<Tokens> {
+    // Removes `_` separators, which may only sit between digits
+    fn strip_separators(digits: &str, radix: u32) -> Option<String> {
+        let valid = !digits.is_empty()
+            && !digits.starts_with('_')
+            && !digits.ends_with('_')
+            && digits.chars().all(|ch| ch == '_' || ch.is_digit(radix));
+        valid.then(|| digits.replace('_', ""))
+    }
+
+    fn tokenize_char_literal(&mut self) -> Result<Tokens, LexErrorKind> {
         self.pos += 1; // Skip the opening single quote
-        let ch = self.input[self.pos..].chars().next()?;
-        self.pos += 1; // Move past the char
-        if self.input[self.pos..].chars().next()? == '\'' {
-            self.pos += 1; // Skip the closing single quote
-            Some(Tokens::CharLiteral(ch))
-        } else {
-            None // Invalid char literal
+        let mut chars = Vec::new();
+        let mut error = None;
+        loop {
+            let ch = match self.input[self.pos..].chars().next() {
+                Some(ch) if ch != '\n' => ch,
+                _ => return Err(LexErrorKind::InvalidCharLiteral), // No closing quote on this line
+            };
+            self.pos += ch.len_utf8();
+            match ch {
+                '\'' => break, // Closing quote found
+                '\\' => match self.read_escape() {
+                    Ok(escaped) => chars.push(escaped),
+                    Err(kind) => { error.get_or_insert(kind); },
+                },
+                _ => chars.push(ch),
+            }
+        }
+        match (error, chars.as_slice()) {
+            (Some(kind), _) => Err(kind),
+            (None, [ch]) => Ok(Tokens::CharLiteral(*ch)),
+            (None, _) => Err(LexErrorKind::InvalidCharLiteral),
+        }
+    }
+
+    // Reads the character after a backslash inside a string or char literal
+    fn read_escape(&mut self) -> Result<char, LexErrorKind> {
+        let ch = match self.input[self.pos..].chars().next() {
+            Some(ch) => ch,
+            None => return Err(LexErrorKind::UnterminatedString),
+        };
+        self.pos += ch.len_utf8();
+        match ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            _ => Err(LexErrorKind::InvalidEscape(ch)),
         }
     }
 
     fn skip_whitespace(&mut self) {
         while self.pos < self.input.len() {
             match self.input[self.pos..].chars().next() {
-                Some(ch) if ch.is_whitespace() => self.pos += 1,
+                Some(ch) if ch.is_whitespace() => self.pos += ch.len_utf8(),
                 _ => break,
             }
         }
     }
 
-    fn is_identifier_char(&self, c: char) -> bool {
-        c.is_alphanumeric() || c == '_'
+    // Identifiers follow Unicode UAX #31: XID_Start or '_' first, then XID_Continue
+    fn is_identifier_start(&self, c: char) -> bool {
+        c.is_xid_start() || c == '_'
     }
 
-    fn peek_char(&self, num: usize) -> Option<&str> {
-        if self.pos + num <= self.input.len() {
-            Some(&self.input[self.pos..self.pos + num])
-        } else {
-            None
-        }
+    fn is_identifier_char(&self, c: char) -> bool {
+        c.is_xid_continue()
     }
 }
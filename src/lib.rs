@@ -1,16 +1,86 @@
+//! An embeddable scripting language for host applications.
+//!
+//! Scripts are compiled with an [`Engine`] and run against a [`Scope`] holding the
+//! globals the host wants to share:
+//!
+//! ```
+//! use safe_script::{DynamicValue, Engine, Scope};
+//!
+//! let engine = Engine::new();
+//! let script = engine.compile("int total = price * 3; total > 10;").unwrap();
+//!
+//! let mut scope = Scope::new();
+//! scope.set("price", DynamicValue::Int(4));
+//! let result = engine.run(&script, &mut scope).unwrap();
+//!
+//! assert_eq!(result.as_bool(), Some(true));
+//! assert_eq!(scope.get("total").and_then(DynamicValue::as_int), Some(12));
+//! ```
+
 // Lexer
 #[cfg(any(test, bench))]
 pub mod lexer;
 #[cfg(not(any(test, bench)))]
 pub(crate) mod lexer;
 
+// Parser
+#[cfg(any(test, bench))]
+pub mod parser;
+#[cfg(not(any(test, bench)))]
+pub(crate) mod parser;
+
+// Interpreter
+#[cfg(any(test, bench))]
+pub mod interpreter;
+#[cfg(not(any(test, bench)))]
+pub(crate) mod interpreter;
+
 // Types
 #[cfg(any(test, bench))]
 pub mod types;
 #[cfg(not(any(test, bench)))]
 pub(crate) mod types;
 
+// Type checker
+#[cfg(any(test, bench))]
+pub mod type_checker;
+#[cfg(not(any(test, bench)))]
+pub(crate) mod type_checker;
+
+// Engine
+mod engine;
+
+// Serde support
+#[cfg(feature = "serde")]
+mod serialization;
+
+// Public API
+pub use engine::Engine;
+pub use types::dynamic_value::DynamicValue;
+pub use types::error::Error;
+pub use types::from_dynamic::FromDynamic;
+pub use types::into_native_function::IntoNativeFunction;
+pub use types::lex_error::LexError;
+pub use types::lex_error_kind::LexErrorKind;
+pub use types::limits::Limits;
+pub use types::native_result::NativeResult;
+pub use types::parse_error::ParseError;
+pub use types::permissions::Permissions;
+pub use types::runtime_error::RuntimeError;
+pub use types::runtime_error_kind::RuntimeErrorKind;
+pub use types::scope::Scope;
+pub use types::script::Script;
+pub use types::span::Span;
+pub use types::stack_frame::StackFrame;
+pub use types::type_error::TypeError;
+pub use types::var_type::VarType;
+#[cfg(feature = "serde")]
+pub use serialization::{from_dynamic, to_dynamic};
+
+// Tests
+#[cfg(test)]
+mod tests;
+
 // Benchmarks
 #[cfg(bench)]
 pub mod benchmarks;
-mod benchmarks;
\ No newline at end of file
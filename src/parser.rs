@@ -0,0 +1,899 @@
+use std::sync::Arc;
+use crate::types::argument::Argument;
+use crate::types::assignment_operator::AssignmentOperator;
+use crate::types::ast::Ast;
+use crate::types::ast_node::AstNode;
+use crate::types::base_functions::BaseFunctions;
+use crate::types::binary_operator::BinaryOperator;
+use crate::types::case_label::CaseLabel;
+use crate::types::expression::Expression;
+use crate::types::format_spec::FormatSpec;
+use crate::types::function_definition::FunctionDefinition;
+use crate::types::interpolation_part::InterpolationPart;
+use crate::types::interpolation_segment::InterpolationSegment;
+use crate::types::numeric::Numeric;
+use crate::types::parameter::Parameter;
+use crate::types::parse_error::ParseError;
+use crate::types::signature::Signature;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::switch_case::SwitchCase;
+use crate::types::tokens::Tokens;
+use crate::types::unary_operator::UnaryOperator;
+use crate::types::var_type::VarType;
+
+// Every level of nesting costs a few stack frames here, in the type checker and in the interpreter
+const MAX_NESTING: usize = 64;
+
+pub struct Parser {
+    tokens: Vec<Spanned<Tokens>>,
+    pos: usize,
+    // Where break, continue and return are allowed; a function body starts afresh
+    loop_depth: usize,
+    switch_depth: usize,
+    in_function: bool,
+    // Open statements, expressions and operator chains, bounded by MAX_NESTING
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Spanned<Tokens>>) -> Self {
+        // Doc comments only mean something directly above a function declaration, so drop the
+        // rest up front, including those above a lambda, where 'fn' is followed by '('
+        let mut kept = Vec::with_capacity(tokens.len());
+        let mut before_function = false;
+        let mut before_parenthesis = false;
+        for token in tokens.into_iter().rev() {
+            match token.node {
+                Tokens::DocComment(_) if !before_function => continue,
+                Tokens::DocComment(_) => {},
+                _ => {
+                    before_function = token.node == Tokens::BaseFunctions(BaseFunctions::Function) && !before_parenthesis;
+                    before_parenthesis = token.node == Tokens::LParentheses;
+                },
+            }
+            kept.push(token);
+        }
+        kept.reverse();
+
+        Parser {
+            tokens: kept,
+            pos: 0,
+            loop_depth: 0,
+            switch_depth: 0,
+            in_function: false,
+            depth: 0,
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Ast, ParseError> {
+        let mut ast = Ast::new();
+        while !self.is_at_end() {
+            ast.statements.push(self.parse_statement()?);
+        }
+        Ok(ast)
+    }
+
+    // Statements
+
+    fn parse_statement(&mut self) -> Result<Spanned<AstNode>, ParseError> {
+        self.nest()?;
+        let start = self.current_span();
+        let statement = match self.peek() {
+            _ if self.at_declaration() => {
+                let declaration = self.parse_var_declaration()?;
+                self.expect(&Tokens::SemiColon, "Expected ';' after variable declaration")?;
+                declaration
+            },
+            Some(Tokens::LBrace) => AstNode::Block(self.parse_block()?),
+            Some(Tokens::BaseFunctions(BaseFunctions::If)) => self.parse_if()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::While)) => self.parse_while()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::For)) => self.parse_for()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::Switch)) => self.parse_switch()?,
+            // fn( starts a lambda, which falls through to an expression statement
+            Some(Tokens::BaseFunctions(BaseFunctions::Function)) if !self.next_is(&Tokens::LParentheses) => self.parse_function()?,
+            Some(Tokens::DocComment(_)) => self.parse_function()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::Return)) => self.parse_return()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::Try)) => self.parse_try()?,
+            Some(Tokens::BaseFunctions(BaseFunctions::Throw)) => {
+                self.advance();
+                let value = self.parse_expression()?;
+                self.expect(&Tokens::SemiColon, "Expected ';' after thrown value")?;
+                AstNode::Throw(value)
+            },
+            Some(Tokens::BaseFunctions(BaseFunctions::Break)) => {
+                self.advance();
+                if self.loop_depth == 0 && self.switch_depth == 0 {
+                    return Err(self.error_at_previous("'break' outside of a loop or switch"));
+                }
+                self.expect(&Tokens::SemiColon, "Expected ';' after 'break'")?;
+                AstNode::Break
+            },
+            Some(Tokens::BaseFunctions(BaseFunctions::Continue)) => {
+                self.advance();
+                if self.loop_depth == 0 {
+                    return Err(self.error_at_previous("'continue' outside of a loop"));
+                }
+                self.expect(&Tokens::SemiColon, "Expected ';' after 'continue'")?;
+                AstNode::Continue
+            },
+            _ => {
+                let expression = self.parse_expression()?;
+                self.expect(&Tokens::SemiColon, "Expected ';' after expression")?;
+                AstNode::Expression(expression)
+            }
+        };
+        self.depth -= 1;
+        Ok(self.spanned(statement, start))
+    }
+
+    fn parse_var_declaration(&mut self) -> Result<AstNode, ParseError> {
+        let var_type = self.parse_type()?;
+        let name = self.expect_identifier("Expected variable name")?;
+        let value = if self.match_token(&Tokens::Equals) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        Ok(AstNode::VarDeclaration { var_type, name, value })
+    }
+
+    fn parse_block(&mut self) -> Result<Vec<Spanned<AstNode>>, ParseError> {
+        self.expect(&Tokens::LBrace, "Expected '{'")?;
+        let mut statements = Vec::new();
+        while !self.check(&Tokens::RBrace) {
+            if self.is_at_end() {
+                return Err(self.error("Expected '}' to close block"));
+            }
+            statements.push(self.parse_statement()?);
+        }
+        self.advance(); // Skip the closing brace
+        Ok(statements)
+    }
+
+    fn parse_if(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'if'
+        let condition = self.parse_condition()?;
+        let then_branch = Box::new(self.parse_statement()?);
+        let else_branch = if self.match_token(&Tokens::BaseFunctions(BaseFunctions::Else)) {
+            Some(Box::new(self.parse_statement()?))
+        } else {
+            None
+        };
+        Ok(AstNode::If { condition, then_branch, else_branch })
+    }
+
+    fn parse_while(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'while'
+        let condition = self.parse_condition()?;
+        let body = Box::new(self.parse_loop_body()?);
+        Ok(AstNode::While { condition, body })
+    }
+
+    fn parse_for(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'for'
+        self.expect(&Tokens::LParentheses, "Expected '(' after 'for'")?;
+
+        let initializer = if self.match_token(&Tokens::SemiColon) {
+            None
+        } else {
+            let start = self.current_span();
+            let initializer = match self.peek() {
+                _ if self.at_declaration() => self.parse_var_declaration()?,
+                _ => AstNode::Expression(self.parse_expression()?),
+            };
+            let initializer = self.spanned(initializer, start);
+            self.expect(&Tokens::SemiColon, "Expected ';' after loop initializer")?;
+            Some(Box::new(initializer))
+        };
+
+        let condition = if self.check(&Tokens::SemiColon) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.expect(&Tokens::SemiColon, "Expected ';' after loop condition")?;
+
+        let update = if self.check(&Tokens::RParentheses) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.expect(&Tokens::RParentheses, "Expected ')' after for clauses")?;
+
+        let body = Box::new(self.parse_loop_body()?);
+        Ok(AstNode::For { initializer, condition, update, body })
+    }
+
+    fn parse_switch(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'switch'
+        let subject = self.parse_condition()?;
+        self.expect(&Tokens::LBrace, "Expected '{' after switch subject")?;
+
+        self.switch_depth += 1;
+        let arms = self.parse_switch_arms(subject);
+        self.switch_depth -= 1;
+        arms
+    }
+
+    fn parse_switch_arms(&mut self, subject: Spanned<Expression>) -> Result<AstNode, ParseError> {
+        let mut cases = Vec::new();
+        let mut default = None;
+        while !self.match_token(&Tokens::RBrace) {
+            if self.match_token(&Tokens::BaseFunctions(BaseFunctions::Default)) {
+                if default.is_some() {
+                    return Err(self.error_at_previous("Duplicate 'default' in switch"));
+                }
+                self.expect(&Tokens::Colon, "Expected ':' after 'default'")?;
+                default = Some(self.parse_case_body()?);
+                continue;
+            }
+            if !self.match_token(&Tokens::BaseFunctions(BaseFunctions::Case)) {
+                return Err(self.error("Expected 'case' or 'default' in switch body"));
+            }
+            let mut labels = vec![self.parse_case_label()?];
+            while self.match_token(&Tokens::Comma) {
+                labels.push(self.parse_case_label()?);
+            }
+            self.expect(&Tokens::Colon, "Expected ':' after case value")?;
+            let body = self.parse_case_body()?;
+            cases.push(SwitchCase { labels, body });
+        }
+        Ok(AstNode::Switch { subject, cases, default })
+    }
+
+    // A value, or an inclusive range written start..end
+    fn parse_case_label(&mut self) -> Result<CaseLabel, ParseError> {
+        let start = self.parse_expression()?;
+        if !self.at_range() {
+            return Ok(CaseLabel::Value(start));
+        }
+        self.pos += 2;
+        let end = self.parse_expression()?;
+        Ok(CaseLabel::Range { start, end })
+    }
+
+    // Statements up to the next arm or the end of the switch
+    fn parse_case_body(&mut self) -> Result<Vec<Spanned<AstNode>>, ParseError> {
+        let mut body = Vec::new();
+        while !matches!(
+            self.peek(),
+            Some(Tokens::BaseFunctions(BaseFunctions::Case | BaseFunctions::Default) | Tokens::RBrace)
+        ) {
+            if self.is_at_end() {
+                return Err(self.error("Expected '}' to close switch"));
+            }
+            body.push(self.parse_statement()?);
+        }
+        Ok(body)
+    }
+
+    fn parse_loop_body(&mut self) -> Result<Spanned<AstNode>, ParseError> {
+        self.loop_depth += 1;
+        let body = self.parse_statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_return(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'return'
+        if !self.in_function {
+            return Err(self.error_at_previous("'return' outside of a function"));
+        }
+        let value = if self.check(&Tokens::SemiColon) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.expect(&Tokens::SemiColon, "Expected ';' after return value")?;
+        Ok(AstNode::Return(value))
+    }
+
+    // try { } catch (e) { } finally { }, with a catch, a finally or both
+    fn parse_try(&mut self) -> Result<AstNode, ParseError> {
+        self.advance(); // Skip 'try'
+        let body = self.parse_block()?;
+        let catch = if self.match_token(&Tokens::BaseFunctions(BaseFunctions::Catch)) {
+            self.expect(&Tokens::LParentheses, "Expected '(' after 'catch'")?;
+            let name = self.expect_identifier("Expected a name for the caught error")?;
+            self.expect(&Tokens::RParentheses, "Expected ')' after the caught error's name")?;
+            Some((name, self.parse_block()?))
+        } else {
+            None
+        };
+        let finally = if self.match_token(&Tokens::BaseFunctions(BaseFunctions::Finally)) {
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error("Expected 'catch' or 'finally' after 'try' block"));
+        }
+        Ok(AstNode::Try { body, catch, finally })
+    }
+
+    fn parse_function(&mut self) -> Result<AstNode, ParseError> {
+        let mut lines = Vec::new();
+        while let Some(Tokens::DocComment(line)) = self.peek() {
+            lines.push(line.clone());
+            self.advance();
+        }
+        let doc = if lines.is_empty() { None } else { Some(lines.join("\n")) };
+
+        self.advance(); // Skip 'fn'
+        let name = self.expect_identifier("Expected function name")?;
+        self.expect(&Tokens::LParentheses, "Expected '(' after function name")?;
+        let signature = self.parse_signature()?;
+        let body = self.parse_function_body(|parser| parser.parse_block())?;
+        Ok(AstNode::Function { doc, name, signature, body })
+    }
+
+    // A lambda, fn(x) => x * 2, whose body is a single expression or a block
+    fn parse_lambda(&mut self) -> Result<Expression, ParseError> {
+        self.expect(&Tokens::LParentheses, "Expected '(' after 'fn'")?;
+        let signature = self.parse_signature()?;
+        let body = self.parse_function_body(|parser| {
+            if parser.check(&Tokens::LBrace) {
+                return parser.parse_block();
+            }
+            parser.expect(&Tokens::FatArrow, "Expected '=>' or a block after lambda parameters")?;
+            let value = parser.parse_expression()?;
+            let span = value.span;
+            Ok(vec![Spanned::new(AstNode::Return(Some(value)), span)])
+        })?;
+        Ok(Expression::Lambda(Arc::new(FunctionDefinition { name: "lambda".to_string(), signature, body })))
+    }
+
+    // Parameters after the opening parenthesis, then an optional -> return type
+    fn parse_signature(&mut self) -> Result<Signature, ParseError> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        if !self.check(&Tokens::RParentheses) {
+            loop {
+                let var_type = match self.peek() {
+                    Some(Tokens::BaseVariables(_)) => Some(self.parse_type()?),
+                    _ if self.at_declaration() => Some(self.parse_type()?),
+                    _ => None,
+                };
+                let name = self.expect_identifier("Expected parameter name")?;
+                if parameters.iter().any(|parameter| parameter.name == name) {
+                    return Err(self.error_at_previous(&format!("Duplicate parameter '{}'", name)));
+                }
+                let default = if self.match_token(&Tokens::Equals) {
+                    Some(self.parse_expression()?)
+                } else if parameters.last().is_some_and(|parameter| parameter.default.is_some()) {
+                    return Err(self.error_at_previous(&format!("Parameter '{}' needs a default, as it follows one that has one", name)));
+                } else {
+                    None
+                };
+                parameters.push(Parameter { var_type, name, default });
+                if !self.match_token(&Tokens::Comma) {
+                    break;
+                }
+            }
+        }
+        self.expect(&Tokens::RParentheses, "Expected ')' after parameters")?;
+        let return_type = if self.match_token(&Tokens::Arrow) {
+            match self.peek().and_then(type_name) {
+                Some(_) => Some(self.parse_type()?),
+                None => return Err(self.error("Expected return type after '->'")),
+            }
+        } else {
+            None
+        };
+        Ok(Signature { parameters, return_type })
+    }
+
+    // Loops and switches around a function do not reach into its body
+    fn parse_function_body<F>(&mut self, parse: F) -> Result<Vec<Spanned<AstNode>>, ParseError>
+    where
+        F: FnOnce(&mut Self) -> Result<Vec<Spanned<AstNode>>, ParseError>,
+    {
+        let context = (self.loop_depth, self.switch_depth, self.in_function);
+        (self.loop_depth, self.switch_depth, self.in_function) = (0, 0, true);
+        let body = parse(self);
+        (self.loop_depth, self.switch_depth, self.in_function) = context;
+        body
+    }
+
+    fn parse_condition(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        self.expect(&Tokens::LParentheses, "Expected '('")?;
+        let condition = self.parse_expression()?;
+        self.expect(&Tokens::RParentheses, "Expected ')'")?;
+        Ok(condition)
+    }
+
+    // A base type, optionally with element types: array<int>, int[] or object<string, float>
+    fn parse_type(&mut self) -> Result<VarType, ParseError> {
+        let outer = self.depth;
+        self.nest()?;
+        let Some(var_type) = self.advance().as_ref().and_then(type_name) else {
+            return Err(self.error_at_previous("Expected a type"));
+        };
+        let mut var_type = match var_type {
+            VarType::Array(None) if self.match_token(&Tokens::Less) => {
+                let element = self.parse_type()?;
+                self.expect(&Tokens::Greater, "Expected '>' after array element type")?;
+                VarType::array_of(element)
+            },
+            VarType::Object(None) if self.match_token(&Tokens::Less) => {
+                if self.parse_type()? != VarType::String {
+                    return Err(self.error_at_previous("Object keys are always strings"));
+                }
+                self.expect(&Tokens::Comma, "Expected ',' after object key type")?;
+                let element = self.parse_type()?;
+                self.expect(&Tokens::Greater, "Expected '>' after object value type")?;
+                VarType::object_of(element)
+            },
+            var_type => var_type,
+        };
+        while self.check(&Tokens::LSquareBracket) && self.tokens.get(self.pos + 1).map(|token| &token.node) == Some(&Tokens::RSquareBracket) {
+            self.pos += 2;
+            self.nest()?;
+            var_type = VarType::array_of(var_type);
+        }
+        self.depth = outer;
+        Ok(var_type)
+    }
+
+    // Expressions, from lowest to highest precedence
+
+    fn parse_expression(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        self.nest()?;
+        let expression = self.parse_assignment()?;
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn parse_assignment(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        let start = self.current_span();
+        let target = self.parse_binary(0)?;
+        let operator = match self.peek() {
+            Some(Tokens::Equals) => AssignmentOperator::Assign,
+            Some(Tokens::PlusEqual) => AssignmentOperator::AddAssign,
+            Some(Tokens::MinusEqual) => AssignmentOperator::SubtractAssign,
+            Some(Tokens::StarEqual) => AssignmentOperator::MultiplyAssign,
+            Some(Tokens::ForwardSlashEqual) => AssignmentOperator::DivideAssign,
+            _ => return Ok(target),
+        };
+        if !target.node.is_place() {
+            return Err(ParseError::new("Invalid assignment target", target.span));
+        }
+        self.advance();
+        // `x =- 1` is `x = -1`, but `=*` and `=/` can only be a reversed compound operator
+        if let Some(reversed) = self.reversed_compound_operator() {
+            return Err(self.error(&format!("Unexpected '={0}', did you mean '{0}='?", reversed)));
+        }
+        self.nest()?;
+        let value = self.parse_assignment()?; // Right associative
+        self.depth -= 1;
+        let assignment = Expression::Assign {
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+        };
+        Ok(self.spanned(assignment, start))
+    }
+
+    fn reversed_compound_operator(&self) -> Option<char> {
+        let operator = match self.peek() {
+            Some(Tokens::Star) => '*',
+            Some(Tokens::ForwardSlash) => '/',
+            _ => return None,
+        };
+        (self.previous_span().end == self.current_span().start).then_some(operator)
+    }
+
+    // Binding strength of the binary operator at the cursor, loosest first
+    fn binary_operator(&self) -> Option<(BinaryOperator, u8)> {
+        let operator = match self.peek()? {
+            Tokens::Or => (BinaryOperator::Or, 0),
+            Tokens::AndAnd => (BinaryOperator::And, 1),
+            Tokens::Pipe => (BinaryOperator::BitOr, 2),
+            Tokens::Caret => (BinaryOperator::BitXor, 3),
+            Tokens::Ampersand => (BinaryOperator::BitAnd, 4),
+            Tokens::EqualEqual => (BinaryOperator::Equal, 5),
+            Tokens::NotEqual => (BinaryOperator::NotEqual, 5),
+            Tokens::Less => (BinaryOperator::Less, 6),
+            Tokens::LessEqual => (BinaryOperator::LessEqual, 6),
+            Tokens::Greater => (BinaryOperator::Greater, 6),
+            Tokens::GreaterEqual => (BinaryOperator::GreaterEqual, 6),
+            Tokens::Plus => (BinaryOperator::Add, 7),
+            Tokens::Minus => (BinaryOperator::Subtract, 7),
+            Tokens::Star => (BinaryOperator::Multiply, 8),
+            Tokens::ForwardSlash => (BinaryOperator::Divide, 8),
+            Tokens::Percent => (BinaryOperator::Modulo, 8),
+            _ => return None,
+        };
+        Some(operator)
+    }
+
+    // Parses operators binding at least as tightly as min_precedence. Each run of operators sharing
+    // a precedence becomes one flat chain, which nests a single level however long it is
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Spanned<Expression>, ParseError> {
+        let start = self.current_span();
+        let outer = self.depth;
+        let mut left = self.parse_unary()?;
+        while let Some((_, precedence)) = self.binary_operator().filter(|(_, precedence)| *precedence >= min_precedence) {
+            self.nest()?;
+            let mut rest = Vec::new();
+            while let Some((operator, _)) = self.binary_operator().filter(|(_, next)| *next == precedence) {
+                self.advance();
+                rest.push((operator, self.parse_binary(precedence + 1)?));
+            }
+            left = self.spanned(Expression::Binary { first: Box::new(left), rest }, start);
+        }
+        self.depth = outer;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        let start = self.current_span();
+        let operator = match self.peek() {
+            Some(Tokens::Bang) => UnaryOperator::Not,
+            Some(Tokens::Minus) => UnaryOperator::Negate,
+            Some(Tokens::Plus) => UnaryOperator::Plus,
+            Some(Tokens::PlusPlus) => UnaryOperator::PreIncrement,
+            Some(Tokens::MinusMinus) => UnaryOperator::PreDecrement,
+            _ => return self.parse_postfix(),
+        };
+        self.advance();
+        // A negated literal is folded, as i64::MIN can only be written that way
+        if operator == UnaryOperator::Negate && self.check(&Tokens::NumberLiteral(Numeric::Int(i64::MIN))) && !self.next_is_postfix() {
+            self.advance();
+            return Ok(self.spanned(Expression::Number(Numeric::Int(i64::MIN)), start));
+        }
+        self.nest()?;
+        let operand = self.parse_unary()?;
+        self.depth -= 1;
+        if matches!(operator, UnaryOperator::PreIncrement | UnaryOperator::PreDecrement) && !operand.node.is_place() {
+            return Err(ParseError::new("Increment and decrement need a variable", operand.span));
+        }
+        let unary = Expression::Unary { operator, operand: Box::new(operand) };
+        Ok(self.spanned(unary, start))
+    }
+
+    // Indexing, member access and postfix increments, which all bind tighter than prefix operators
+    fn parse_postfix(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        let start = self.current_span();
+        let outer = self.depth;
+        let mut expression = self.parse_primary()?;
+        loop {
+            let postfix = match self.peek() {
+                Some(Tokens::LSquareBracket) => {
+                    self.advance();
+                    let index = self.parse_expression()?;
+                    self.expect(&Tokens::RSquareBracket, "Expected ']' after index")?;
+                    Expression::Index { target: Box::new(expression), index: Box::new(index) }
+                },
+                // Calling the value of any other expression, as in handlers[0](event)
+                Some(Tokens::LParentheses) => {
+                    let arguments = self.parse_call_arguments()?;
+                    Expression::Invoke { target: Box::new(expression), arguments }
+                },
+                Some(Tokens::Period) if !self.at_range() => {
+                    self.advance();
+                    let name = self.expect_identifier("Expected member name after '.'")?;
+                    Expression::Member { target: Box::new(expression), name }
+                },
+                Some(Tokens::PlusPlus | Tokens::MinusMinus) => {
+                    if !expression.node.is_place() {
+                        return Err(ParseError::new("Increment and decrement need a variable", expression.span));
+                    }
+                    let operator = match self.advance() {
+                        Some(Tokens::PlusPlus) => UnaryOperator::PostIncrement,
+                        _ => UnaryOperator::PostDecrement,
+                    };
+                    Expression::Unary { operator, operand: Box::new(expression) }
+                },
+                _ => {
+                    self.depth = outer;
+                    return Ok(expression);
+                },
+            };
+            expression = self.spanned(postfix, start);
+            self.nest()?;
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Spanned<Expression>, ParseError> {
+        let start = self.current_span();
+        let expression = match self.advance() {
+            Some(Tokens::NumberLiteral(Numeric::Int(i64::MIN))) => {
+                return Err(self.error_at_previous(&format!("Number {} is out of range", i64::MIN.unsigned_abs())));
+            },
+            Some(Tokens::NumberLiteral(number)) => Expression::Number(number),
+            Some(Tokens::StringLiteral(string)) => Expression::StringLiteral(string),
+            Some(Tokens::InterpolatedString(parts)) => Expression::Interpolated(self.parse_interpolation(parts)?),
+            Some(Tokens::BoolLiteral(boolean)) => Expression::Bool(boolean),
+            Some(Tokens::CharLiteral(ch)) => Expression::Char(ch),
+            Some(Tokens::BaseVariables(VarType::Null)) => Expression::Null,
+            Some(Tokens::Identifier(name)) => {
+                if self.check(&Tokens::LParentheses) {
+                    let arguments = self.parse_call_arguments()?;
+                    Expression::Call { name, arguments }
+                } else {
+                    Expression::Identifier(name)
+                }
+            },
+            Some(Tokens::BaseFunctions(BaseFunctions::Function)) => self.parse_lambda()?,
+            // Either convertTo(value, type) or convertTo<type>(value)
+            Some(Tokens::BaseFunctions(BaseFunctions::ConvertTo)) => {
+                if self.check(&Tokens::Less) {
+                    self.advance();
+                    let target = self.parse_type()?;
+                    self.expect(&Tokens::Greater, "Expected '>' after target type")?;
+                    self.expect(&Tokens::LParentheses, "Expected '(' after 'convertTo<type>'")?;
+                    let value = self.parse_expression()?;
+                    self.expect(&Tokens::RParentheses, "Expected ')' after value to convert")?;
+                    Expression::ConvertTo { value: Box::new(value), target }
+                } else {
+                    self.expect(&Tokens::LParentheses, "Expected '(' after 'convertTo'")?;
+                    let value = self.parse_expression()?;
+                    self.expect(&Tokens::Comma, "Expected ',' after value to convert")?;
+                    let target = self.parse_type()?;
+                    self.expect(&Tokens::RParentheses, "Expected ')' after target type")?;
+                    Expression::ConvertTo { value: Box::new(value), target }
+                }
+            },
+            Some(Tokens::BaseFunctions(function @ BaseFunctions::WriteLn)) => {
+                let arguments = self.parse_arguments()?;
+                Expression::BaseCall { function, arguments }
+            },
+            Some(Tokens::LParentheses) => {
+                let expression = self.parse_expression()?;
+                self.expect(&Tokens::RParentheses, "Expected ')' after expression")?;
+                expression.node
+            },
+            Some(Tokens::LSquareBracket) => Expression::Array(self.parse_array()?),
+            Some(Tokens::LBrace) => Expression::Object(self.parse_object()?),
+            Some(token) => return Err(self.error_at_previous(&format!("Unexpected token {:?}", token))),
+            None => return Err(self.error("Unexpected end of input")),
+        };
+        Ok(self.spanned(expression, start))
+    }
+
+    // Each hole holds its own token stream, parsed as a single expression
+    fn parse_interpolation(&self, parts: Vec<InterpolationPart>) -> Result<Vec<InterpolationSegment>, ParseError> {
+        let mut segments = Vec::with_capacity(parts.len());
+        for part in parts {
+            let segment = match part {
+                InterpolationPart::Literal(text) => InterpolationSegment::Literal(text),
+                InterpolationPart::Hole { tokens, format } => {
+                    let mut parser = Parser { tokens, pos: 0, loop_depth: 0, switch_depth: 0, in_function: false, depth: self.depth };
+                    let expression = parser.parse_expression()?;
+                    if !parser.is_at_end() {
+                        return Err(parser.error("Expected '}' after interpolated expression"));
+                    }
+                    let format = match format {
+                        Some(format) => match FormatSpec::from_str(&format) {
+                            Some(spec) => Some(spec),
+                            None => return Err(self.error_at_previous(&format!("Invalid format specifier '{}'", format))),
+                        },
+                        None => None,
+                    };
+                    InterpolationSegment::Hole { expression, format }
+                },
+            };
+            segments.push(segment);
+        }
+        Ok(segments)
+    }
+
+    // The opening '[' has already been consumed; a trailing comma is allowed
+    fn parse_array(&mut self) -> Result<Vec<Spanned<Expression>>, ParseError> {
+        let mut elements = Vec::new();
+        while !self.match_token(&Tokens::RSquareBracket) {
+            elements.push(self.parse_expression()?);
+            if !self.match_token(&Tokens::Comma) {
+                self.expect(&Tokens::RSquareBracket, "Expected ',' or ']' in array literal")?;
+                break;
+            }
+        }
+        Ok(elements)
+    }
+
+    // The opening '{' has already been consumed. Keys are names or string literals
+    fn parse_object(&mut self) -> Result<Vec<(String, Spanned<Expression>)>, ParseError> {
+        let mut entries: Vec<(String, Spanned<Expression>)> = Vec::new();
+        while !self.match_token(&Tokens::RBrace) {
+            let key = match self.advance() {
+                Some(Tokens::Identifier(key) | Tokens::StringLiteral(key)) => key,
+                _ => return Err(self.error_at_previous("Expected a key in object literal")),
+            };
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(self.error_at_previous(&format!("Duplicate key '{}' in object literal", key)));
+            }
+            self.expect(&Tokens::Colon, "Expected ':' after object key")?;
+            entries.push((key, self.parse_expression()?));
+            if !self.match_token(&Tokens::Comma) {
+                self.expect(&Tokens::RBrace, "Expected ',' or '}' in object literal")?;
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<Spanned<Expression>>, ParseError> {
+        self.expect(&Tokens::LParentheses, "Expected '('")?;
+        let mut arguments = Vec::new();
+        if !self.check(&Tokens::RParentheses) {
+            loop {
+                arguments.push(self.parse_expression()?);
+                if !self.match_token(&Tokens::Comma) {
+                    break;
+                }
+            }
+        }
+        self.expect(&Tokens::RParentheses, "Expected ')' after arguments")?;
+        Ok(arguments)
+    }
+
+    // Positional arguments, then any named ones written name: value
+    fn parse_call_arguments(&mut self) -> Result<Vec<Argument>, ParseError> {
+        self.expect(&Tokens::LParentheses, "Expected '('")?;
+        let mut arguments: Vec<Argument> = Vec::new();
+        if !self.check(&Tokens::RParentheses) {
+            loop {
+                let name = match self.peek() {
+                    Some(Tokens::Identifier(name)) if self.next_is(&Tokens::Colon) => {
+                        let name = name.clone();
+                        self.pos += 2;
+                        Some(name)
+                    },
+                    _ => None,
+                };
+                let value = self.parse_expression()?;
+                if name.is_none() && arguments.last().is_some_and(|argument| argument.name.is_some()) {
+                    return Err(ParseError::new("Positional arguments must come before named ones", value.span));
+                }
+                arguments.push(Argument { name, value });
+                if !self.match_token(&Tokens::Comma) {
+                    break;
+                }
+            }
+        }
+        self.expect(&Tokens::RParentheses, "Expected ')' after arguments")?;
+        Ok(arguments)
+    }
+
+    // Token helpers
+
+    fn peek(&self) -> Option<&Tokens> {
+        self.tokens.get(self.pos).map(|token| &token.node)
+    }
+
+    fn advance(&mut self) -> Option<Tokens> {
+        let token = self.tokens.get(self.pos).map(|token| token.node.clone());
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn check(&self, token: &Tokens) -> bool {
+        self.peek() == Some(token)
+    }
+
+    // Whether the token after the next one is `token`
+    fn next_is(&self, token: &Tokens) -> bool {
+        self.tokens.get(self.pos + 1).map(|next| &next.node) == Some(token)
+    }
+
+    // Whether a variable declaration starts here. A contextual type name only starts one when
+    // a name or a type suffix follows, as in `object o`, `object[] list` or `object<string, int> o`
+    fn at_declaration(&self) -> bool {
+        let after = |offset: usize| self.tokens.get(self.pos + offset).map(|token| &token.node);
+        match self.peek() {
+            Some(Tokens::BaseVariables(var_type)) => *var_type != VarType::Null,
+            Some(Tokens::Identifier(name)) if VarType::CONTEXTUAL.contains(&name.as_str()) => match after(1) {
+                Some(Tokens::Identifier(_)) => true,
+                Some(Tokens::LSquareBracket) => after(2) == Some(&Tokens::RSquareBracket),
+                Some(Tokens::Less) => after(2).and_then(type_name).is_some(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // Whether the token after the current one continues it as an index, call, member access or postfix increment
+    fn next_is_postfix(&self) -> bool {
+        match self.tokens.get(self.pos + 1).map(|token| &token.node) {
+            Some(Tokens::LSquareBracket | Tokens::LParentheses | Tokens::PlusPlus | Tokens::MinusMinus) => true,
+            Some(Tokens::Period) => !self.range_at(self.pos + 1),
+            _ => false,
+        }
+    }
+
+    fn match_token(&mut self, token: &Tokens) -> bool {
+        if self.check(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Tokens, message: &str) -> Result<(), ParseError> {
+        if self.match_token(token) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn expect_identifier(&mut self, message: &str) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Tokens::Identifier(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            },
+            _ => Err(self.error(message)),
+        }
+    }
+
+    // Two adjacent periods, which the lexer leaves as separate tokens
+    fn at_range(&self) -> bool {
+        self.range_at(self.pos)
+    }
+
+    fn range_at(&self, index: usize) -> bool {
+        match (self.tokens.get(index), self.tokens.get(index + 1)) {
+            (Some(first), Some(second)) => {
+                first.node == Tokens::Period && second.node == Tokens::Period && first.span.end == second.span.start
+            },
+            _ => false,
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    // Span of the next token, or an empty span just past the input at the end
+    fn current_span(&self) -> Span {
+        match self.tokens.get(self.pos) {
+            Some(token) => token.span,
+            None => match self.tokens.last() {
+                Some(last) => Span::new(last.span.end, last.span.end, last.span.line, last.span.column + (last.span.end - last.span.start)),
+                None => Span::new(0, 0, 1, 1),
+            },
+        }
+    }
+
+    fn previous_span(&self) -> Span {
+        match self.pos.checked_sub(1).and_then(|index| self.tokens.get(index)) {
+            Some(token) => token.span,
+            None => self.current_span(),
+        }
+    }
+
+    fn spanned<T>(&self, node: T, start: Span) -> Spanned<T> {
+        Spanned::new(node, start.to(self.previous_span()))
+    }
+
+    fn nest(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(self.error(&format!("Nested more than {} levels deep", MAX_NESTING)));
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.current_span())
+    }
+
+    fn error_at_previous(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.previous_span())
+    }
+}
+
+// The type a token names, counting contextual type names, which lex as identifiers
+fn type_name(token: &Tokens) -> Option<VarType> {
+    match token {
+        Tokens::BaseVariables(var_type) => Some(var_type.clone()),
+        Tokens::Identifier(name) if VarType::CONTEXTUAL.contains(&name.as_str()) => VarType::from_str(name),
+        _ => None,
+    }
+}
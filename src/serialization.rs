@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::fmt;
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, Serializer};
+use serde::Deserialize;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+
+/// Converts any serializable Rust value into a [`DynamicValue`], for example to pass a struct
+/// to a script as a global.
+///
+/// Structs and maps become objects, sequences and tuples become arrays, `u8` becomes a byte
+/// and `None` or `()` becomes `null`. Enum variants carrying data become a single-entry object
+/// keyed by the variant name.
+pub fn to_dynamic<T: Serialize>(value: &T) -> Result<DynamicValue, RuntimeError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts a [`DynamicValue`], such as a script result, back into a Rust value.
+pub fn from_dynamic<T: DeserializeOwned>(value: DynamicValue) -> Result<T, RuntimeError> {
+    T::deserialize(value)
+}
+
+impl ser::Error for RuntimeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        RuntimeError::new(RuntimeErrorKind::Serialization(message.to_string()))
+    }
+}
+
+impl de::Error for RuntimeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        RuntimeError::new(RuntimeErrorKind::Serialization(message.to_string()))
+    }
+}
+
+// Serializing a value
+
+impl Serialize for DynamicValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DynamicValue::Node(_) => Err(ser::Error::custom("a syntax node cannot be serialized")),
+            DynamicValue::Function(_) => Err(ser::Error::custom("a function cannot be serialized")),
+            DynamicValue::Error(_) => Err(ser::Error::custom("an error cannot be serialized")),
+            DynamicValue::Int(value) => serializer.serialize_i64(*value),
+            DynamicValue::Float(value) => serializer.serialize_f64(*value),
+            DynamicValue::String(value) => serializer.serialize_str(value),
+            DynamicValue::Bool(value) => serializer.serialize_bool(*value),
+            DynamicValue::Char(value) => serializer.serialize_char(*value),
+            DynamicValue::Byte(value) => serializer.serialize_u8(*value),
+            DynamicValue::Array(values) => serializer.collect_seq(values),
+            DynamicValue::Object(values) => {
+                // Sorted, so the output does not depend on hash order
+                let mut keys = values.keys().collect::<Vec<_>>();
+                keys.sort();
+                let mut map = serializer.serialize_map(Some(keys.len()))?;
+                for key in keys {
+                    map.serialize_entry(key, &values[key])?;
+                }
+                map.end()
+            },
+            DynamicValue::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+// Deserializing a value from any self-describing format
+
+impl<'de> Deserialize<'de> for DynamicValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = DynamicValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a value a script can hold")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Int(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<DynamicValue, E> {
+        i64::try_from(value).map(DynamicValue::Int).map_err(|_| E::custom(format!("{} does not fit in an int", value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Float(value))
+    }
+
+    fn visit_char<E: de::Error>(self, value: char) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Char(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::String(value))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Array(value.iter().copied().map(DynamicValue::Byte).collect()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<DynamicValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<DynamicValue, E> {
+        Ok(DynamicValue::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut sequence: A) -> Result<DynamicValue, A::Error> {
+        let mut values = Vec::with_capacity(sequence.size_hint().unwrap_or(0));
+        while let Some(value) = sequence.next_element()? {
+            values.push(value);
+        }
+        Ok(DynamicValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DynamicValue, A::Error> {
+        let mut values = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, DynamicValue>()? {
+            values.insert(key, value);
+        }
+        Ok(DynamicValue::Object(values))
+    }
+}
+
+// Reading Rust values out of a DynamicValue
+
+impl<'de> Deserializer<'de> for DynamicValue {
+    type Error = RuntimeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuntimeError> {
+        match self {
+            DynamicValue::Node(_) => Err(de::Error::custom("a syntax node cannot be deserialized")),
+            DynamicValue::Function(_) => Err(de::Error::custom("a function cannot be deserialized")),
+            DynamicValue::Error(_) => Err(de::Error::custom("an error cannot be deserialized")),
+            DynamicValue::Int(value) => visitor.visit_i64(value),
+            DynamicValue::Float(value) => visitor.visit_f64(value),
+            DynamicValue::String(value) => visitor.visit_string(value),
+            DynamicValue::Bool(value) => visitor.visit_bool(value),
+            DynamicValue::Char(value) => visitor.visit_char(value),
+            DynamicValue::Byte(value) => visitor.visit_u8(value),
+            DynamicValue::Array(values) => {
+                let mut sequence = SeqDeserializer::new(values.into_iter());
+                let value = visitor.visit_seq(&mut sequence)?;
+                sequence.end()?;
+                Ok(value)
+            },
+            DynamicValue::Object(values) => {
+                let mut map = MapDeserializer::new(values.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+            DynamicValue::Null => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuntimeError> {
+        match self {
+            DynamicValue::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, RuntimeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Unit variants are strings, and variants with data are single-entry objects
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        match self {
+            DynamicValue::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            DynamicValue::Object(values) if values.len() == 1 => {
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(values.into_iter())))
+            },
+            other => Err(de::Error::custom(format!("expected an enum variant, found {:?}", other.get_type()))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, RuntimeError> for DynamicValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// Writing Rust values into a DynamicValue
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = VariantSerializer<ArraySerializer>;
+    type SerializeMap = ObjectSerializer;
+    type SerializeStruct = ObjectSerializer;
+    type SerializeStructVariant = VariantSerializer<ObjectSerializer>;
+
+    fn serialize_bool(self, value: bool) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value as i64))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value as i64))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value as i64))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Byte(value))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value as i64))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Int(value as i64))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<DynamicValue, RuntimeError> {
+        ValueVisitor.visit_u64(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Float(value as f64))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Float(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Char(value))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<DynamicValue, RuntimeError> {
+        ValueVisitor.visit_bytes(value)
+    }
+
+    fn serialize_none(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<DynamicValue, RuntimeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<DynamicValue, RuntimeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<DynamicValue, RuntimeError> {
+        Ok(single_entry(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, length: Option<usize>) -> Result<ArraySerializer, RuntimeError> {
+        Ok(ArraySerializer { values: Vec::with_capacity(length.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, length: usize) -> Result<ArraySerializer, RuntimeError> {
+        self.serialize_seq(Some(length))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, length: usize) -> Result<ArraySerializer, RuntimeError> {
+        self.serialize_seq(Some(length))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        length: usize,
+    ) -> Result<VariantSerializer<ArraySerializer>, RuntimeError> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(length))? })
+    }
+
+    fn serialize_map(self, length: Option<usize>) -> Result<ObjectSerializer, RuntimeError> {
+        Ok(ObjectSerializer { values: HashMap::with_capacity(length.unwrap_or(0)), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, length: usize) -> Result<ObjectSerializer, RuntimeError> {
+        self.serialize_map(Some(length))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        length: usize,
+    ) -> Result<VariantSerializer<ObjectSerializer>, RuntimeError> {
+        Ok(VariantSerializer { variant, inner: self.serialize_map(Some(length))? })
+    }
+}
+
+fn single_entry(key: &str, value: DynamicValue) -> DynamicValue {
+    DynamicValue::Object(HashMap::from([(key.to_string(), value)]))
+}
+
+struct ArraySerializer {
+    values: Vec<DynamicValue>,
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Array(self.values))
+    }
+}
+
+impl ser::SerializeTuple for ArraySerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for ArraySerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct ObjectSerializer {
+    values: HashMap<String, DynamicValue>,
+    key: Option<String>,
+}
+
+impl SerializeMap for ObjectSerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    // Object keys are strings, so numbers, chars and unit variants are written as text
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RuntimeError> {
+        self.key = Some(match key.serialize(ValueSerializer)? {
+            DynamicValue::String(key) => key,
+            key @ (DynamicValue::Int(_) | DynamicValue::Byte(_) | DynamicValue::Char(_) | DynamicValue::Bool(_)) => key.to_string(),
+            other => return Err(ser::Error::custom(format!("object keys must be strings, found {:?}", other.get_type()))),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        let key = self.key.take().ok_or_else(|| <RuntimeError as ser::Error>::custom("map value serialized before its key"))?;
+        self.values.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Object(self.values))
+    }
+}
+
+impl ser::SerializeStruct for ObjectSerializer {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), RuntimeError> {
+        self.values.insert(key.to_string(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(DynamicValue::Object(self.values))
+    }
+}
+
+// Wraps the fields of a tuple or struct variant in an object keyed by the variant name
+struct VariantSerializer<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<ArraySerializer> {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(single_entry(self.variant, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<ObjectSerializer> {
+    type Ok = DynamicValue;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), RuntimeError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(single_entry(self.variant, ser::SerializeStruct::end(self.inner)?))
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::{DynamicValue, Engine, Error, LexErrorKind, Limits, Permissions, RuntimeError, RuntimeErrorKind, Scope, Script, VarType};
+
+#[test]
+fn test_compile_once_run_many() {
+    let engine = Engine::new();
+    let script = engine.compile("x * 2;").unwrap();
+
+    for x in 1..4 {
+        let mut scope = Scope::new();
+        scope.set("x", DynamicValue::Int(x));
+        assert_eq!(engine.run(&script, &mut scope).unwrap().as_int(), Some(x * 2));
+    }
+}
+
+#[test]
+fn test_share_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Engine>();
+    assert_send_sync::<Script>();
+    assert_send_sync::<Scope>();
+
+    let mut engine = Engine::new();
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&output);
+    engine.on_print(move |line| sink.lock().unwrap().push(line.to_string()));
+    engine.register_typed_fn("double", |value: i64| value * 2);
+    let script = engine.compile("function twice = fn(int x) => double(x); writeLn(twice(x)); twice(x);").unwrap();
+
+    let results = std::thread::scope(|threads| {
+        let runs = (1..4)
+            .map(|x| {
+                let (engine, script) = (&engine, &script);
+                threads.spawn(move || {
+                    let mut scope = Scope::new();
+                    scope.set("x", DynamicValue::Int(x));
+                    engine.run(script, &mut scope).unwrap().as_int()
+                })
+            })
+            .collect::<Vec<_>>();
+        runs.into_iter().map(|run| run.join().unwrap()).collect::<Vec<_>>()
+    });
+    assert_eq!(results, vec![Some(2), Some(4), Some(6)]);
+    assert_eq!(output.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_scope_round_trip() {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+    scope.set("count", DynamicValue::Int(1));
+
+    engine.eval("count++; string label = \"done\";", &mut scope).unwrap();
+
+    assert_eq!(scope.get("count").and_then(DynamicValue::as_int), Some(2));
+    assert_eq!(scope.get("label").and_then(DynamicValue::as_string), Some("done"));
+
+    // Declared globals persist into the next run, so declaring them again fails
+    let error = engine.eval("string label;", &mut scope).err().unwrap();
+    assert!(matches!(error, Error::Runtime(error) if error.kind == RuntimeErrorKind::AlreadyDeclared("label".to_string())));
+
+    assert!(scope.remove("label").is_some());
+    assert!(!scope.contains("label"));
+}
+
+#[test]
+fn test_host_variables_are_untyped() {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+    scope.set("value", DynamicValue::Int(1));
+
+    engine.eval("value = \"now a string\";", &mut scope).unwrap();
+
+    assert_eq!(scope.get("value").and_then(DynamicValue::as_string), Some("now a string"));
+}
+
+#[test]
+fn test_print_handler() {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&output);
+    let mut engine = Engine::new();
+    engine.on_print(move |line| sink.lock().unwrap().push(line.to_string()));
+
+    engine.eval("writeLn(\"a\"); writeLn(1, 2);", &mut Scope::new()).unwrap();
+
+    assert_eq!(*output.lock().unwrap(), vec!["a", "1 2"]);
+}
+
+#[test]
+fn test_errors_by_stage() {
+    let engine = Engine::new();
+
+    let error = engine.compile("int x = ~;").unwrap_err();
+    assert!(matches!(&error, Error::Lex(errors) if errors[0].kind == LexErrorKind::UnknownCharacter('~')));
+    assert_eq!(error.to_string(), "Unknown character '~' at 1:9");
+
+    let error = engine.compile("int x = 1").unwrap_err();
+    assert!(matches!(error, Error::Parse(_)));
+
+    let error = engine.compile("while (true) { }\nbreak;").unwrap_err();
+    assert_eq!(error.to_string(), "'break' outside of a loop or switch at 2:1");
+
+    let error = engine.eval("1 / 0;", &mut Scope::new()).err().unwrap();
+    assert_eq!(error.to_string(), "Division by zero at 1:1");
+}
+
+#[test]
+fn test_convert_to_from_host() {
+    let price = DynamicValue::from("19.5").convert_to(VarType::Float).unwrap();
+    assert_eq!(price.as_float(), Some(19.5));
+
+    let error = DynamicValue::Int(300).convert_to(VarType::Byte).err().unwrap();
+    assert_eq!(error.to_string(), "Cannot convert 300 to Byte: it is out of range");
+
+    // Objects round-trip through arrays of [key, value] pairs
+    let mut order = HashMap::new();
+    order.insert("qty".to_string(), DynamicValue::Int(2));
+    order.insert("id".to_string(), DynamicValue::from("a1"));
+    let pairs = DynamicValue::Object(order).convert_to(VarType::Array(None)).unwrap();
+    let keys = pairs.as_array().unwrap().iter().map(|pair| pair.as_array().unwrap()[0].as_string().unwrap()).collect::<Vec<_>>();
+    assert_eq!(keys, vec!["id", "qty"]);
+
+    let order = pairs.convert_to(VarType::Object(None)).unwrap();
+    assert_eq!(order.as_object().unwrap()["qty"].as_int(), Some(2));
+    assert!(DynamicValue::Array(vec![DynamicValue::Int(1)]).convert_to(VarType::Object(None)).is_err());
+}
+
+#[test]
+fn test_values_compare_and_display() {
+    let engine = Engine::new();
+    assert_eq!(engine.eval("1 + 2;", &mut Scope::new()).unwrap(), DynamicValue::Int(3));
+    assert_eq!(engine.eval("0.0 / 0.0;", &mut Scope::new()).unwrap(), DynamicValue::Float(f64::NAN));
+    assert_ne!(DynamicValue::Int(1), DynamicValue::Float(1.0));
+
+    let mut order = HashMap::new();
+    order.insert("sku".to_string(), DynamicValue::from("A-1"));
+    order.insert("qty".to_string(), DynamicValue::Int(2));
+    order.insert("tags".to_string(), DynamicValue::Array(vec![DynamicValue::Char('x'), DynamicValue::Null]));
+    let order = DynamicValue::Object(order);
+    assert_eq!(order, order.clone());
+    assert_eq!(order.to_string(), "{qty: 2, sku: \"A-1\", tags: ['x', null]}");
+
+    // Top-level strings are written as they are, matching writeLn
+    assert_eq!(DynamicValue::from("A-1").to_string(), "A-1");
+    assert_eq!(DynamicValue::Float(2.0).to_string(), "2.0");
+    assert_eq!(format!("{:?}", DynamicValue::Int(7)), "Int(7)");
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_float_values() {
+    let value = Engine::new().eval("7 / 2.0;", &mut Scope::new()).unwrap();
+    assert_eq!(value, DynamicValue::Float(3.5));
+    assert_eq!(value.get_type(), VarType::Float);
+    assert_eq!(value.as_float(), Some(3.5));
+
+    // The old accessor still works for hosts that have not migrated yet
+    assert_eq!(value.as_long(), Some(3.5));
+}
+
+#[test]
+fn test_register_fn() {
+    let mut engine = Engine::new();
+    engine.register_fn("sum", |args: &[DynamicValue]| {
+        args.iter().try_fold(0, |total, arg| match arg.as_int() {
+            Some(value) => Ok(total + value),
+            None => Err("sum only takes ints".into()),
+        }).map(DynamicValue::Int)
+    });
+
+    assert_eq!(engine.eval("sum(1, 2, 3) * 2;", &mut Scope::new()).unwrap().as_int(), Some(12));
+
+    let error = engine.eval("int x = 1;\nsum(x, true);", &mut Scope::new()).err().unwrap();
+    match error {
+        Error::Runtime(error) => {
+            assert_eq!(error.kind, RuntimeErrorKind::Native("sum only takes ints".to_string()));
+            assert_eq!(error.to_string(), "sum only takes ints at 2:1");
+        },
+        other => panic!("Expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_register_typed_fn() {
+    let mut engine = Engine::new();
+    engine
+        .register_typed_fn("greet", |name: String, times: i64| name.repeat(times as usize))
+        .register_typed_fn("half", |value: f64| value / 2.0)
+        .register_typed_fn("lookupCustomer", |id: i64| -> Result<String, RuntimeError> {
+            match id {
+                1 => Ok("Ann".to_string()),
+                _ => Err(format!("No customer {}", id).into()),
+            }
+        })
+        .register_typed_fn("orDefault", |value: Option<i64>| value.unwrap_or(-1))
+        .register_typed_fn("answer", || 42);
+
+    let mut scope = Scope::new();
+    assert_eq!(engine.eval("greet(\"ab\", 3);", &mut scope).unwrap().as_string(), Some("ababab"));
+    assert_eq!(engine.eval("half(5);", &mut scope).unwrap().as_float(), Some(2.5));
+    assert_eq!(engine.eval("lookupCustomer(1);", &mut scope).unwrap().as_string(), Some("Ann"));
+    assert_eq!(engine.eval("orDefault(null) + orDefault(3) + answer();", &mut scope).unwrap().as_int(), Some(44));
+
+    let runtime_error = |source: &str, scope: &mut Scope| match engine.eval(source, scope).err().unwrap() {
+        Error::Runtime(error) => error.kind,
+        other => panic!("Expected a runtime error, got {:?}", other),
+    };
+    assert_eq!(runtime_error("lookupCustomer(2);", &mut Scope::new()), RuntimeErrorKind::Native("No customer 2".to_string()));
+    scope.set("text", DynamicValue::String("x".to_string()));
+    assert_eq!(runtime_error("half(text);", &mut scope), RuntimeErrorKind::TypeMismatch { expected: VarType::Float, found: VarType::String });
+
+    // Calls whose problems are visible in the source fail to compile instead
+    let compile_error = |source: &str| engine.compile(source).err().unwrap().to_string();
+    assert_eq!(compile_error("half();"), "'half' expects 1 argument(s), got 0 at 1:1");
+    assert_eq!(compile_error("half(\"x\");"), "Type mismatch: expected Float, found String at 1:6");
+    assert_eq!(compile_error("orDefault(true);"), "Type mismatch: expected Int, found Bool at 1:11");
+    assert_eq!(compile_error("greet(name: \"a\", times: 2);"), "'greet' has no parameter 'name' at 1:1");
+    assert!(engine.compile("orDefault(null); half(1); half(text);").is_ok());
+}
+
+#[test]
+fn test_untyped_host_functions_are_not_checked() {
+    let mut engine = Engine::new();
+    engine.register_typed_fn("count", |value: i64| value).register_fn("count", |arguments| Ok(DynamicValue::Int(arguments.len() as i64)));
+
+    // Registering a slice function replaces the typed one, signature included
+    assert_eq!(engine.eval("count(\"a\", 2, true);", &mut Scope::new()).unwrap().as_int(), Some(3));
+    let error = engine.eval("count(x: 1);", &mut Scope::new()).err().unwrap();
+    assert!(matches!(error, Error::Runtime(error) if error.kind == RuntimeErrorKind::UnknownParameter {
+        function: "count".to_string(),
+        name: "x".to_string(),
+    }));
+}
+
+#[test]
+fn test_native_functions_share_the_call_path() {
+    let mut engine = Engine::new();
+    engine.register_typed_fn("double", |value: i64| value * 2);
+
+    // Callable from inside script functions, and a script cannot redefine it, which compiling already reports
+    assert_eq!(engine.eval("fn quad(int x) { writeLn(double(double(x))); } quad(2); double(4);", &mut Scope::new()).unwrap().as_int(), Some(8));
+    let error = engine.compile("fn double(int x) { }").err().unwrap();
+    assert!(matches!(&error, Error::Type(errors) if errors[0].message == "'double' is already declared"));
+    let error = engine.compile("function f = fn() { fn double(x) { return 0; } return 0; }; f(); double(1);").err().unwrap();
+    assert!(matches!(&error, Error::Type(errors) if errors[0].message == "'double' is already declared"));
+}
+
+#[test]
+fn test_host_errors_are_catchable() {
+    let mut engine = Engine::new();
+    engine.register_typed_fn("lookupCustomer", |id: i64| -> Result<String, RuntimeError> {
+        match id {
+            1 => Ok("Ann".to_string()),
+            _ => Err(format!("No customer {}", id).into()),
+        }
+    });
+
+    let script = "string name; try { name = lookupCustomer(2); } catch (e) { name = e.kind + \": \" + e.message; } name;";
+    assert_eq!(engine.eval(script, &mut Scope::new()).unwrap().as_string(), Some("Native: No customer 2"));
+
+    // Uncaught, the error reaches the host with the calls that led to it
+    let error = match engine.eval("fn find(int id) { return lookupCustomer(id); }\nfind(2);", &mut Scope::new()).err().unwrap() {
+        Error::Runtime(error) => error,
+        other => panic!("Expected a runtime error, got {:?}", other),
+    };
+    assert_eq!(error.kind, RuntimeErrorKind::Native("No customer 2".to_string()));
+    assert_eq!(error.to_string(), "No customer 2 at 1:26");
+    let frames = error.backtrace.iter().map(|frame| (frame.function.as_str(), frame.call_site.line, frame.call_site.column)).collect::<Vec<_>>();
+    assert_eq!(frames, vec![("lookupCustomer", 1, 26), ("find", 2, 1)]);
+}
+
+#[test]
+fn test_limits_cannot_be_caught() {
+    let limits = Limits { max_operations: Some(1000), ..Limits::default() };
+    let error = run_limited(limits, "int tries = 0; while (true) { try { while (true) { } } catch (e) { tries++; } }");
+    assert_eq!(error, RuntimeErrorKind::OperationLimitExceeded(1000));
+
+    // Nor does finally run on the way out
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_call_depth: Some(10), ..Limits::default() });
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&output);
+    engine.on_print(move |line| sink.lock().unwrap().push(line.to_string()));
+    let error = engine.eval("fn down() { down(); } try { down(); } catch (e) { } finally { writeLn(\"finally\"); }", &mut Scope::new());
+    assert!(matches!(error, Err(Error::Runtime(error)) if error.kind == RuntimeErrorKind::CallDepthExceeded(10) && error.backtrace.len() == 10));
+    assert!(output.lock().unwrap().is_empty());
+    assert!(RuntimeErrorKind::Thrown("x".to_string()).is_catchable());
+}
+
+fn run_limited(limits: Limits, source: &str) -> RuntimeErrorKind {
+    let mut engine = Engine::new();
+    engine.set_limits(limits);
+    match engine.eval(source, &mut Scope::new()).err() {
+        Some(Error::Runtime(error)) => error.kind,
+        other => panic!("Expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_operation_limit() {
+    let limits = Limits { max_operations: Some(1000), ..Limits::default() };
+
+    assert_eq!(run_limited(limits.clone(), "while (true) { }"), RuntimeErrorKind::OperationLimitExceeded(1000));
+
+    // The count starts again on every run
+    let mut engine = Engine::new();
+    engine.set_limits(limits);
+    let script = engine.compile("int x = 0; while (x < 50) { x++; }").unwrap();
+    for _ in 0..3 {
+        assert!(engine.run(&script, &mut Scope::new()).is_ok());
+    }
+}
+
+#[test]
+fn test_call_depth_limit() {
+    let limits = Limits { max_call_depth: Some(10), ..Limits::default() };
+    assert_eq!(run_limited(limits, "fn down(int n) { down(n + 1); } down(0);"), RuntimeErrorKind::CallDepthExceeded(10));
+
+    // Infinite recursion is stopped by the default limit instead of overflowing the stack
+    let error = run_limited(Limits::default(), "fn forever() { forever(); } forever();");
+    assert_eq!(error, RuntimeErrorKind::CallDepthExceeded(Limits::DEFAULT_CALL_DEPTH));
+}
+
+#[test]
+fn test_nesting_limit() {
+    let limit = RuntimeErrorKind::NestingLimitExceeded(Limits::DEFAULT_NESTING);
+
+    // Each call nests a little deeper than the last, well before the call depth limit
+    let nested = format!("fn down(int n) -> int {{ return {}down(n + 1){}; }} down(0);", "-(".repeat(25), ")".repeat(25));
+    assert_eq!(run_limited(Limits::default(), &nested), limit);
+
+    // Values can only be built so deep, however they are built, and catching does not help
+    assert_eq!(run_limited(Limits::default(), "array a = []; while (true) { a = [a]; }"), limit);
+    assert_eq!(run_limited(Limits::default(), "object o = {}; while (true) { o = { inner: o }; }"), limit);
+    assert_eq!(run_limited(Limits::default(), "array a = [[]]; while (true) { a[0] = [a[0]]; }"), limit);
+    assert_eq!(run_limited(Limits::default(), "function f = fn() => 0; while (true) { function g = f; f = fn() => g(); }"), limit);
+    assert_eq!(run_limited(Limits::default(), "array a = []; try { while (true) { a = [a]; } } catch (e) { }"), limit);
+
+    // Shallower values are fine
+    let engine = Engine::new();
+    let value = engine.eval("array a = []; int i = 0; while (i < 100) { a = [a]; i++; } a[0][0] = [1]; a;", &mut Scope::new());
+    assert!(value.is_ok());
+
+    // So are long flat operator chains, which nest a single level
+    let terms = (1..=200).map(|n| n.to_string()).collect::<Vec<_>>();
+    assert_eq!(engine.eval(&format!("{};", terms.join(" + ")), &mut Scope::new()).unwrap().as_int(), Some(20100));
+    let tests = (1..=200).map(|n| format!("x == {}", n)).collect::<Vec<_>>();
+    let script = format!("int x = 150; {};", tests.join(" || "));
+    assert_eq!(engine.eval(&script, &mut Scope::new()).unwrap().as_bool(), Some(true));
+}
+
+#[test]
+fn test_memory_limit() {
+    let limits = Limits { max_memory: Some(1 << 20), ..Limits::default() };
+
+    let error = run_limited(limits.clone(), "string s = \"ab\"; while (true) { s = s + s; }");
+    assert_eq!(error, RuntimeErrorKind::MemoryLimitExceeded(1 << 20));
+
+    let mut engine = Engine::new();
+    engine.set_limits(limits);
+    assert!(engine.eval("string s = \"small\"; s = s + s;", &mut Scope::new()).is_ok());
+}
+
+#[test]
+fn test_timeout() {
+    let started = Instant::now();
+    let limits = Limits { timeout: Some(Duration::from_millis(50)), ..Limits::default() };
+
+    assert_eq!(run_limited(limits, "while (true) { }"), RuntimeErrorKind::Timeout(Duration::from_millis(50)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_capabilities_gate_native_functions() {
+    let mut engine = Engine::new();
+    engine
+        .register_typed_fn("readFile", |path: String| format!("contents of {}", path))
+        .require_capability("readFile", "fs.read")
+        .register_typed_fn("now", || 1700000000);
+
+    let error = engine.eval("now(); readFile(\"a.txt\");", &mut Scope::new()).err().unwrap();
+    match error {
+        Error::Runtime(error) => {
+            assert_eq!(error.kind, RuntimeErrorKind::PermissionDenied {
+                function: "readFile".to_string(),
+                capability: "fs.read".to_string(),
+            });
+            assert_eq!(error.to_string(), "'readFile' needs the 'fs.read' capability, which this run was not granted at 1:8");
+        },
+        other => panic!("Expected a runtime error, got {:?}", other),
+    }
+
+    // Granting a parent capability covers its children
+    engine.set_permissions(Permissions::default().grant("fs"));
+    assert_eq!(engine.eval("readFile(\"a.txt\");", &mut Scope::new()).unwrap().as_string(), Some("contents of a.txt"));
+}
+
+#[test]
+fn test_write_ln_needs_print_capability() {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&output);
+    let mut engine = Engine::new();
+    engine.on_print(move |line| sink.lock().unwrap().push(line.to_string()));
+    engine.set_permissions(Permissions::none());
+
+    let error = engine.eval("writeLn(\"hidden\");", &mut Scope::new()).err().unwrap();
+    assert!(matches!(error, Error::Runtime(error) if error.kind == RuntimeErrorKind::PermissionDenied {
+        function: "writeLn".to_string(),
+        capability: Permissions::PRINT.to_string(),
+    }));
+    assert!(output.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_permissions() {
+    let permissions = Permissions::none().grant("net").grant("fs.read");
+
+    assert!(permissions.allows("net"));
+    assert!(permissions.allows("net.http"));
+    assert!(permissions.allows("fs.read"));
+    assert!(!permissions.allows("fs"));
+    assert!(!permissions.allows("fs.write"));
+    assert!(!permissions.allows("network"));
+    assert!(!permissions.clone().revoke("net").allows("net.http"));
+    assert!(Permissions::all().allows("anything.at.all"));
+    assert!(Permissions::default().allows(Permissions::PRINT));
+}
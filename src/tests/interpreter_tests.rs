@@ -0,0 +1,662 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+use crate::types::span::Span;
+use crate::types::stack_frame::StackFrame;
+use crate::types::var_type::VarType;
+
+fn run(script: &str) -> Result<DynamicValue, RuntimeError> {
+    let mut lexer = Lexer::new(script);
+    let ast = Parser::new(lexer.tokenize().unwrap()).parse().unwrap();
+    Interpreter::new().run(&ast)
+}
+
+fn run_with_output(script: &str) -> (Result<DynamicValue, RuntimeError>, Vec<String>) {
+    let mut lexer = Lexer::new(script);
+    let ast = Parser::new(lexer.tokenize().unwrap()).parse().unwrap();
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&output);
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_print_handler(move |line| sink.borrow_mut().push(line.to_string()));
+    let result = interpreter.run(&ast);
+
+    let lines = output.borrow().clone();
+    (result, lines)
+}
+
+fn run_err(script: &str) -> RuntimeErrorKind {
+    match run(script) {
+        Ok(_) => panic!("Expected a runtime error"),
+        Err(error) => error.kind,
+    }
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(run("1 + 2 * 3;").unwrap().as_int(), Some(7));
+    assert_eq!(run("(1 + 2) * 3;").unwrap().as_int(), Some(9));
+    assert_eq!(run("7 / 2;").unwrap().as_int(), Some(3));
+    assert_eq!(run("7 % 4;").unwrap().as_int(), Some(3));
+    assert_eq!(run("7.0 / 2;").unwrap().as_float(), Some(3.5));
+    assert_eq!(run("-5 + 2;").unwrap().as_int(), Some(-3));
+}
+
+#[test]
+fn test_numeric_literal_forms() {
+    assert_eq!(run("0xFF & 0b1010;").unwrap().as_int(), Some(10));
+    assert_eq!(run("1_000 * 0o10;").unwrap().as_int(), Some(8000));
+    assert_eq!(run("255b;").unwrap().as_byte(), Some(255));
+    assert_eq!(run("2f / 4;").unwrap().as_float(), Some(0.5));
+    assert_eq!(run("2.5e2;").unwrap().as_float(), Some(250.0));
+}
+
+#[test]
+fn test_comparison_and_logic() {
+    assert_eq!(run("1 < 2 && 2 <= 2;").unwrap().as_bool(), Some(true));
+    assert_eq!(run("3 > 4 || 4 >= 5;").unwrap().as_bool(), Some(false));
+    assert_eq!(run("1 == 1.0;").unwrap().as_bool(), Some(true));
+    assert_eq!(run("\"abc\" < \"abd\";").unwrap().as_bool(), Some(true));
+    assert_eq!(run("'a' != 'b';").unwrap().as_bool(), Some(true));
+    assert_eq!(run("!true;").unwrap().as_bool(), Some(false));
+}
+
+#[test]
+fn test_logic_short_circuits() {
+    // The right side would fail with an undefined variable if it were evaluated
+    assert_eq!(run("false && missing;").unwrap().as_bool(), Some(false));
+    assert_eq!(run("true || missing;").unwrap().as_bool(), Some(true));
+}
+
+#[test]
+fn test_bitwise_operators() {
+    assert_eq!(run("12 & 10;").unwrap().as_int(), Some(8));
+    assert_eq!(run("12 | 10;").unwrap().as_int(), Some(14));
+    assert_eq!(run("12 ^ 10;").unwrap().as_int(), Some(6));
+    assert_eq!(run("true ^ true;").unwrap().as_bool(), Some(false));
+}
+
+#[test]
+fn test_string_concatenation() {
+    assert_eq!(run("\"total: \" + 42;").unwrap().as_string(), Some("total: 42"));
+    assert_eq!(run("\"pi \" + 3.5;").unwrap().as_string(), Some("pi 3.5"));
+}
+
+#[test]
+fn test_variables_and_assignment() {
+    assert_eq!(run("int x = 1; x = x + 4; x;").unwrap().as_int(), Some(5));
+    assert_eq!(run("int x = 10; x += 5; x -= 3; x *= 2; x /= 4; x;").unwrap().as_int(), Some(6));
+    assert_eq!(run("int x = 1; int y = x++; y * 10 + x;").unwrap().as_int(), Some(12));
+    assert_eq!(run("int x = 1; int y = ++x; y * 10 + x;").unwrap().as_int(), Some(22));
+    assert_eq!(run("int x = 5; x =- 1; x;").unwrap().as_int(), Some(-1));
+    assert_eq!(run("int x = 5; x =+ 2; x;").unwrap().as_int(), Some(2));
+}
+
+#[test]
+fn test_declaration_defaults_and_widening() {
+    assert_eq!(run("int x; x;").unwrap().as_int(), Some(0));
+    assert_eq!(run("string s; s;").unwrap().as_string(), Some(""));
+    assert_eq!(run("float f = 2; f;").unwrap().as_float(), Some(2.0));
+    assert_eq!(run("byte b = 255; b;").unwrap().as_byte(), Some(255));
+}
+
+#[test]
+fn test_declared_types_are_enforced() {
+    assert_eq!(run_err("int x = \"hello\";"), RuntimeErrorKind::TypeMismatch {
+        expected: VarType::Int,
+        found: VarType::String,
+    });
+    assert_eq!(run_err("bool b; b = 1;"), RuntimeErrorKind::TypeMismatch {
+        expected: VarType::Bool,
+        found: VarType::Int,
+    });
+    assert_eq!(run_err("byte b = 256;"), RuntimeErrorKind::Overflow);
+}
+
+#[test]
+fn test_scopes() {
+    assert_eq!(run("int x = 1; { int x = 2; x = 3; } x;").unwrap().as_int(), Some(1));
+    assert_eq!(run("int x = 1; { x = 3; } x;").unwrap().as_int(), Some(3));
+    assert_eq!(run_err("{ int y = 1; } y;"), RuntimeErrorKind::UndefinedVariable("y".to_string()));
+    assert_eq!(run_err("int x; int x;"), RuntimeErrorKind::AlreadyDeclared("x".to_string()));
+}
+
+#[test]
+fn test_if_else() {
+    let script = "int x = 5; string s; if (x > 3) { s = \"big\"; } else { s = \"small\"; } s;";
+    assert_eq!(run(script).unwrap().as_string(), Some("big"));
+
+    let script = "int x = 1; string s; if (x > 3) s = \"big\"; else if (x > 0) s = \"some\"; else s = \"none\"; s;";
+    assert_eq!(run(script).unwrap().as_string(), Some("some"));
+}
+
+#[test]
+fn test_conditions_must_be_bool() {
+    assert_eq!(run_err("if (1) { }"), RuntimeErrorKind::TypeMismatch {
+        expected: VarType::Bool,
+        found: VarType::Int,
+    });
+}
+
+#[test]
+fn test_while() {
+    let (result, output) = run_with_output("int x = 0; while (x != 3) { writeLn(x); x++; } x;");
+
+    assert_eq!(result.unwrap().as_int(), Some(3));
+    assert_eq!(output, vec!["0", "1", "2"]);
+}
+
+#[test]
+fn test_for() {
+    let script = "int total = 0; for (int i = 1; i <= 100; i++) { total += i; } total;";
+    assert_eq!(run(script).unwrap().as_int(), Some(5050));
+
+    // The loop variable does not leak out of the loop
+    assert_eq!(run_err("for (int i = 0; i < 1; i++) { } i;"), RuntimeErrorKind::UndefinedVariable("i".to_string()));
+}
+
+#[test]
+fn test_switch_case() {
+    let script = "int x = 2; int k = 0; switch (x) { case 1: { k = 8; } case 2: { k = 9; } case 3: { k = 10; } } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(9));
+
+    let script = "string s = \"b\"; int k = 0; switch (s) { case \"a\": k = 1; case \"b\": k = 2; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(2));
+
+    let script = "int k = 0; switch (5) { case 1: k = 1; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(0));
+}
+
+#[test]
+fn test_switch_default() {
+    let script = "int k = 0; switch (5) { default: k = -1; case 1: k = 1; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(-1));
+
+    let script = "int k = 0; switch (1) { default: k = -1; case 1: k = 1; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(1));
+}
+
+#[test]
+fn test_switch_labels() {
+    let script = "fn kind(int n) { switch (n) { case 0: return \"zero\"; case 1, 2, 3: return \"few\"; case 4..9: return \"some\"; default: return \"many\"; } }";
+    let (_, output) = run_with_output(&format!("{} writeLn(kind(0), kind(2), kind(4), kind(9), kind(10), kind(-1));", script));
+    assert_eq!(output, vec!["zero few some some many many"]);
+
+    let script = "fn class(char c) { switch (c) { case 'a'..'z': return 1; case 'A'..'Z': return 2; case '_': return 3; } return 0; } class('q') * 1000 + class('Z') * 100 + class('_') * 10 + class('!');";
+    assert_eq!(run(script).unwrap().as_int(), Some(1230));
+
+    let script = "string s = \"beta\"; int k = 0; switch (s) { case \"alpha\", \"beta\": k = 1; case \"gamma\": k = 2; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(1));
+
+    // Numbers match across types, ranges included, but other types never fall into a numeric range
+    let script = "int k = 0; switch (2.5) { case 1..2: k = 1; case 2..3: k = 2; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(2));
+    let script = "int k = 0; switch (\"5\") { case 1..9: k = 1; } k;";
+    assert_eq!(run(script).unwrap().as_int(), Some(0));
+    assert_eq!(
+        run_err("switch (1) { case true..false: }"),
+        RuntimeErrorKind::InvalidOperation("Case range bounds must both be numbers or both be chars".to_string())
+    );
+}
+
+#[test]
+fn test_switch_never_falls_through() {
+    // Only the first matching arm runs, whether or not its body ends in break
+    let (_, output) = run_with_output("switch (1) { case 1: writeLn(\"one\"); case 1, 2: writeLn(\"again\"); default: writeLn(\"default\"); }");
+    assert_eq!(output, vec!["one"]);
+
+    // An empty arm does nothing rather than running the next one
+    let (_, output) = run_with_output("switch (1) { case 1: case 2: writeLn(\"two\"); }");
+    assert!(output.is_empty());
+
+    let (_, output) = run_with_output("switch (3) { case 3: writeLn(\"three\"); break; writeLn(\"unreached\"); case 4: writeLn(\"four\"); }");
+    assert_eq!(output, vec!["three"]);
+}
+
+#[test]
+fn test_break_and_continue() {
+    let (_, output) = run_with_output("for (int i = 0; i < 10; i++) { if (i == 4) { break; } if (i % 2 == 0) { continue; } writeLn(i); }");
+    assert_eq!(output, vec!["1", "3"]);
+
+    let script = "int i = 0; int total = 0; while (true) { i++; if (i > 5) break; if (i == 2) continue; total += i; } total;";
+    assert_eq!(run(script).unwrap().as_int(), Some(13));
+
+    // break only leaves the innermost loop
+    let script = "int count = 0; for (int i = 0; i < 3; i++) { for (int j = 0; j < 3; j++) { if (j == 1) break; count++; } } count;";
+    assert_eq!(run(script).unwrap().as_int(), Some(3));
+}
+
+#[test]
+fn test_break_inside_switch_leaves_the_switch() {
+    let (_, output) = run_with_output("for (int i = 0; i < 3; i++) { switch (i) { case 1: break; default: writeLn(i); } writeLn(\"after\"); }");
+    assert_eq!(output, vec!["0", "after", "after", "2", "after"]);
+
+    let (_, output) = run_with_output("for (int i = 0; i < 3; i++) { switch (i) { case 1: continue; } writeLn(i); }");
+    assert_eq!(output, vec!["0", "2"]);
+}
+
+#[test]
+fn test_return() {
+    let script = "fn sign(int n) { if (n < 0) { return -1; } if (n == 0) return 0; return 1; } sign(-5) + sign(0) * 10 + sign(9) * 100;";
+    assert_eq!(run(script).unwrap().as_int(), Some(99));
+
+    // return leaves loops and switches inside the function, and a bare return yields Null
+    let script = "fn find(int target) { for (int i = 0; ; i++) { switch (i) { case target: return i * 2; } } } find(4);";
+    assert_eq!(run(script).unwrap().as_int(), Some(8));
+    let (result, output) = run_with_output("fn early() { writeLn(1); return; writeLn(2); } early();");
+    assert!(result.unwrap().as_null());
+    assert_eq!(output, vec!["1"]);
+
+    let script = "fn fact(int n) { if (n <= 1) { return 1; } return n * fact(n - 1); } fact(10);";
+    assert_eq!(run(script).unwrap().as_int(), Some(3628800));
+}
+
+#[test]
+fn test_functions() {
+    let (result, output) = run_with_output("fn greet(string name) { writeLn(\"Hello, \" + name); } greet(\"Ann\"); greet(\"Bo\");");
+
+    assert!(result.unwrap().as_null());
+    assert_eq!(output, vec!["Hello, Ann", "Hello, Bo"]);
+}
+
+#[test]
+fn test_functions_are_hoisted() {
+    let (_, output) = run_with_output("show(1); fn show(int x) { writeLn(x); }");
+
+    assert_eq!(output, vec!["1"]);
+}
+
+#[test]
+fn test_recursion_and_globals() {
+    let script = "int calls = 0; fn count(int n) { calls++; if (n > 0) { count(n - 1); } } count(4); calls;";
+    assert_eq!(run(script).unwrap().as_int(), Some(5));
+}
+
+#[test]
+fn test_functions_cannot_see_caller_locals() {
+    let script = "fn peek() { writeLn(secret); } { int secret = 1; peek(); }";
+    assert_eq!(run_err(script), RuntimeErrorKind::UndefinedVariable("secret".to_string()));
+}
+
+#[test]
+fn test_function_argument_checks() {
+    assert_eq!(run_err("fn f(int a) { } f();"), RuntimeErrorKind::ArgumentCount {
+        function: "f".to_string(),
+        expected: 1,
+        found: 0,
+    });
+    assert_eq!(run_err("fn f(int a) { } f(true);"), RuntimeErrorKind::TypeMismatch {
+        expected: VarType::Int,
+        found: VarType::Bool,
+    });
+    assert_eq!(run_err("missing();"), RuntimeErrorKind::UndefinedFunction("missing".to_string()));
+}
+
+#[test]
+fn test_return_types() {
+    let script = "fn average(int a, int b) -> float { return (a + b) / 2; } average(2, 3);";
+    assert_eq!(run(script).unwrap(), DynamicValue::Float(2.0));
+
+    assert_eq!(run_err("fn name() -> string { return 1; } name();"), RuntimeErrorKind::TypeMismatch {
+        expected: VarType::String,
+        found: VarType::Int,
+    });
+    // Falling off the end returns null whatever the declared type
+    assert!(run("fn nothing() -> int { } nothing();").unwrap().as_null());
+}
+
+#[test]
+fn test_default_and_named_arguments() {
+    let script = "fn describe(string name, int age = 30, string city = \"Oslo\") { return $\"{name} {age} {city}\"; }";
+    let (_, output) = run_with_output(&format!(
+        "{} writeLn(describe(\"Ann\")); writeLn(describe(\"Bo\", 41)); writeLn(describe(city: \"Rome\", name: \"Cy\")); writeLn(describe(\"Di\", city: \"Nice\"));",
+        script
+    ));
+    assert_eq!(output, vec!["Ann 30 Oslo", "Bo 41 Oslo", "Cy 30 Rome", "Di 30 Nice"]);
+
+    // Defaults run at each call, in the callee, and can use earlier parameters
+    let script = "int calls = 0; fn next() { calls++; return calls; } fn pair(int a, int b = a * next()) { return a + b; } pair(2) + pair(2) * 100 + pair(2, 0) * 10000;";
+    assert_eq!(run(script).unwrap().as_int(), Some(20604));
+
+    // Defaults are coerced to the parameter type like any argument
+    assert_eq!(run("fn f(float x = 1) { return x; } f();").unwrap(), DynamicValue::Float(1.0));
+}
+
+#[test]
+fn test_named_argument_errors() {
+    let function = "fn add(int a, int b = 1) { return a + b; }";
+    let error = |call: &str| run_err(&format!("{} {}", function, call));
+
+    assert_eq!(error("add();"), RuntimeErrorKind::ArgumentCount { function: "add".to_string(), expected: 1, found: 0 });
+    assert_eq!(error("add(1, 2, 3);"), RuntimeErrorKind::ArgumentCount { function: "add".to_string(), expected: 2, found: 3 });
+    assert_eq!(error("add(b: 2);"), RuntimeErrorKind::MissingArgument { function: "add".to_string(), name: "a".to_string() });
+    assert_eq!(error("add(1, c: 2);"), RuntimeErrorKind::UnknownParameter { function: "add".to_string(), name: "c".to_string() });
+    assert_eq!(error("add(1, a: 2);"), RuntimeErrorKind::DuplicateArgument { function: "add".to_string(), name: "a".to_string() });
+    assert_eq!(
+        RuntimeErrorKind::DuplicateArgument { function: "add".to_string(), name: "a".to_string() }.to_string(),
+        "Argument 'a' is passed to 'add' more than once"
+    );
+}
+
+#[test]
+fn test_lambdas() {
+    assert_eq!(run("function double = fn(int x) => x * 2; double(21);").unwrap().as_int(), Some(42));
+    assert_eq!(run("fn(a, b) => a + b;").unwrap().to_string(), "<fn lambda>");
+    assert_eq!(run("(fn(x) { return x + 1; })(1);").unwrap().as_int(), Some(2));
+
+    // Lambdas can be returned, stored in collections and passed to other functions
+    let script = "fn adder(int n) { return fn(x) => x + n; } array ops = [adder(1), adder(10)]; object named = { twice: fn(x) => x * 2 }; named.twice(ops[1](ops[0](1)));";
+    assert_eq!(run(script).unwrap().as_int(), Some(24));
+    let script = "fn apply(f, value) { return f(value); } fn square(int x) { return x * x; } apply(square, 3) + apply(fn(x) => -x, 3);";
+    assert_eq!(run(script).unwrap().as_int(), Some(6));
+
+    // Lambda bodies can declare functions, but not over a host function
+    assert_eq!(run("function f = fn() { fn helper() { return 1; } return helper(); }; f();").unwrap().as_int(), Some(1));
+    let tokens = Lexer::new("function f = fn() { fn sendAlert(x) { return \"script\"; } return 0; }; f(); sendAlert(1);").tokenize().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.register_native("sendAlert", Arc::new(|_: &[DynamicValue]| Ok(DynamicValue::String("host".to_string()))));
+    assert_eq!(interpreter.run(&ast).unwrap_err().kind, RuntimeErrorKind::AlreadyDeclared("sendAlert".to_string()));
+}
+
+#[test]
+fn test_closure_captures() {
+    // Locals are captured by value when the lambda is created
+    let script = "array fns = []; for (int i = 0; i < 3; i++) { fns = fns + [fn() => i * 10]; } fns[0]() + fns[1]() + fns[2]();";
+    assert_eq!(run(script).unwrap().as_int(), Some(30));
+    let script = "fn make() { int n = 1; function f = fn() => n; n = 2; return f; } make()();";
+    assert_eq!(run(script).unwrap().as_int(), Some(1));
+
+    // Globals stay shared, and changes to a captured copy do not leak out
+    let script = "int total = 0; { int step = 5; function add = fn() { total += step; step = 100; }; add(); add(); } total;";
+    assert_eq!(run(script).unwrap().as_int(), Some(10));
+
+    // Parameters shadow captured names
+    assert_eq!(run("int r = 0; { int x = 1; function f = fn(x) => x; r = f(7); } r;").unwrap().as_int(), Some(7));
+}
+
+#[test]
+fn test_callbacks() {
+    let library = "
+        fn map(array items, f) { array out = []; for (int i = 0; i < items.length; i++) { out = out + [f(items[i])]; } return out; }
+        fn filter(array items, keep) { array out = []; for (int i = 0; i < items.length; i++) { if (keep(items[i])) out = out + [items[i]]; } return out; }
+        fn sort(array items, less) {
+            for (int i = 1; i < items.length; i++) {
+                for (int j = i; j > 0 && less(items[j], items[j - 1]); j--) { int swap = items[j]; items[j] = items[j - 1]; items[j - 1] = swap; }
+            }
+            return items;
+        }";
+    let script = format!("{} int limit = 2; sort(map(filter([3, 1, 4, 1, 5], fn(x) => x > limit), fn(x) => x * 10), fn(a, b) => a > b);", library);
+    assert_eq!(run(&script).unwrap().to_string(), "[50, 40, 30]");
+}
+
+#[test]
+fn test_try_catch() {
+    let script = "string caught = \"\"; try { int x = 1;\nx = x / 0; } catch (e) { caught = $\"{e.kind}: {e.message} at {e.line}:{e.column}\"; } caught;";
+    assert_eq!(run(script).unwrap().as_string(), Some("DivisionByZero: Division by zero at 2:5"));
+    let script = "string caught = \"\"; try { throw \"bad input\"; } catch (e) { caught = e.kind + \" \" + e.message; } caught;";
+    assert_eq!(run(script).unwrap().as_string(), Some("Thrown bad input"));
+
+    // Rethrowing keeps the original error, and an uncaught throw reaches the host
+    let script = "string kind = \"\"; try { try { [1][5]; } catch (e) { throw e; } } catch (outer) { kind = outer.kind; } kind;";
+    assert_eq!(run(script).unwrap().as_string(), Some("IndexOutOfBounds"));
+    assert_eq!(run_err("try { throw \"first\"; } catch (e) { throw \"second\"; }"), RuntimeErrorKind::Thrown("second".to_string()));
+
+    // The caught error is an ordinary value, scoped to its handler
+    let (_, output) = run_with_output("error saved; try { throw \"kept\"; } catch (e) { saved = e; } writeLn(saved);");
+    assert_eq!(output, vec!["Thrown: kept"]);
+    assert_eq!(run_err("try { throw \"x\"; } catch (e) { } e;"), RuntimeErrorKind::UndefinedVariable("e".to_string()));
+
+    assert_eq!(run_err("throw 1;"), RuntimeErrorKind::InvalidOperation("Cannot throw Int".to_string()));
+    assert_eq!(run_err("try { throw \"x\"; } catch (e) { e.code; }"), RuntimeErrorKind::InvalidOperation("Error has no member 'code'".to_string()));
+}
+
+#[test]
+fn test_finally() {
+    let script = "
+        fn attempt(int n) {
+            try {
+                if (n == 0) throw \"zero\";
+                return 10 / n;
+            } catch (e) {
+                writeLn(\"caught\", e.message);
+            } finally {
+                writeLn(\"finally\", n);
+            }
+            return -1;
+        }
+        attempt(2) + attempt(0);";
+    let (result, output) = run_with_output(script);
+    assert_eq!(result.unwrap().as_int(), Some(4));
+    assert_eq!(output, vec!["finally 2", "caught zero", "finally 0"]);
+
+    // Without a catch the error continues once finally has run
+    let (result, output) = run_with_output("try { throw \"lost\"; } finally { writeLn(\"cleanup\"); }");
+    assert_eq!(result.unwrap_err().kind, RuntimeErrorKind::Thrown("lost".to_string()));
+    assert_eq!(output, vec!["cleanup"]);
+
+    // A break, continue or return in finally replaces the error or value leaving the try
+    assert_eq!(run("fn f() { try { throw \"x\"; } finally { return 2; } } f();").unwrap().as_int(), Some(2));
+    let script = "int runs = 0; for (int i = 0; i < 5; i++) { try { if (i == 3) break; } finally { runs++; } } runs;";
+    assert_eq!(run(script).unwrap().as_int(), Some(4));
+    let script = "int seen = 0; while (seen < 3) { try { seen++; throw \"skip\"; } finally { continue; } } seen;";
+    assert_eq!(run(script).unwrap().as_int(), Some(3));
+}
+
+#[test]
+fn test_error_backtrace() {
+    let script = "fn inner() { throw \"deep\"; }\nfn outer() { inner(); }\nouter();";
+    let error = run(script).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::Thrown("deep".to_string()));
+    assert_eq!(error.span.map(|span| (span.line, span.column)), Some((1, 14)));
+    let frames = error.backtrace.iter().map(StackFrame::to_string).collect::<Vec<_>>();
+    assert_eq!(frames, vec!["inner called at 2:14", "outer called at 3:1"]);
+
+    // Scripts read the same stack, and a lambda's frame is named after it
+    let script = "array stack = []; fn run(f) { f(); } try { run(fn() => 1 / 0); } catch (e) { stack = e.stack; } stack;";
+    assert_eq!(run(script).unwrap().to_string(), "[\"lambda called at 1:31\", \"run called at 1:44\"]");
+    let (_, output) = run_with_output("try { 1 / 0; } catch (e) { writeLn(e.stack); }");
+    assert_eq!(output, vec!["[]"]);
+}
+
+#[test]
+fn test_function_value_errors() {
+    assert_eq!(run_err("int x = 1; x(2);"), RuntimeErrorKind::InvalidOperation("'x' is not a function".to_string()));
+    assert_eq!(run_err("[1][0](2);"), RuntimeErrorKind::InvalidOperation("Cannot call Int".to_string()));
+    assert_eq!(run_err("function f = fn(int x) => x; f();"), RuntimeErrorKind::ArgumentCount {
+        function: "lambda".to_string(),
+        expected: 1,
+        found: 0,
+    });
+    assert_eq!(run_err("function f = 1;"), RuntimeErrorKind::TypeMismatch { expected: VarType::Function, found: VarType::Int });
+
+    // Functions compare by identity; a named function is always the same value
+    assert_eq!(run("fn f() { } f == f;").unwrap().as_bool(), Some(true));
+    assert_eq!(run("function a = fn() => 1; function b = fn() => 1; a == a && a != b;").unwrap().as_bool(), Some(true));
+}
+
+#[test]
+fn test_write_ln_formatting() {
+    let (_, output) = run_with_output("writeLn(1, 2.5, \"s\", 'c', true, null);");
+
+    assert_eq!(output, vec!["1 2.5 s c true null"]);
+}
+
+#[test]
+fn test_convert_to() {
+    assert_eq!(run("convertTo(\"42\", int);").unwrap().as_int(), Some(42));
+    assert_eq!(run("convertTo(3.9, int);").unwrap().as_int(), Some(3));
+    assert_eq!(run("convertTo(7, string);").unwrap().as_string(), Some("7"));
+    assert!(matches!(run_err("convertTo(\"abc\", int);"), RuntimeErrorKind::ConversionFailed { .. }));
+}
+
+#[test]
+fn test_convert_to_matrix() {
+    assert_eq!(run("convertTo<float>(\" 2.5 \");").unwrap().as_float(), Some(2.5));
+    assert_eq!(run("convertTo<float>(true);").unwrap().as_float(), Some(1.0));
+    assert_eq!(run("convertTo<int>(-3.9);").unwrap().as_int(), Some(-3));
+    assert_eq!(run("convertTo<int>('A');").unwrap().as_int(), Some(65));
+    assert_eq!(run("convertTo<char>(97);").unwrap().as_char(), Some('a'));
+    assert_eq!(run("convertTo<char>(\"z\");").unwrap().as_char(), Some('z'));
+    assert_eq!(run("convertTo<byte>(\"255\");").unwrap().as_byte(), Some(255));
+    assert_eq!(run("convertTo<byte>(65.7);").unwrap().as_byte(), Some(65));
+    assert_eq!(run("convertTo<bool>(2);").unwrap().as_bool(), Some(true));
+    assert_eq!(run("convertTo<bool>(0.0);").unwrap().as_bool(), Some(false));
+    assert_eq!(run("convertTo<bool>(\"false\");").unwrap().as_bool(), Some(false));
+    assert_eq!(run("convertTo<string>(2.0);").unwrap().as_string(), Some("2.0"));
+    assert!(run("convertTo<int>(null);").unwrap().as_null());
+
+    let chars = run("convertTo<array>(\"hi\");").unwrap();
+    let chars = chars.as_array().unwrap().iter().map(|ch| ch.as_char().unwrap()).collect::<String>();
+    assert_eq!(chars, "hi");
+}
+
+#[test]
+fn test_convert_to_failures() {
+    let reason = |script: &str| match run_err(script) {
+        RuntimeErrorKind::ConversionFailed { reason, .. } => reason,
+        other => panic!("expected a conversion failure, got {:?}", other),
+    };
+
+    assert_eq!(reason("convertTo<byte>(300);"), "it is out of range");
+    assert_eq!(reason("convertTo<byte>(-1);"), "it is out of range");
+    assert_eq!(reason("convertTo<int>(0.0 / 0.0);"), "it is not a finite number");
+    assert_eq!(reason("convertTo<int>(1e300);"), "it is out of range");
+    assert_eq!(reason("convertTo<int>(\"12abc\");"), "it is not a whole number");
+    assert_eq!(reason("convertTo<float>(\"inf\");"), "it is not a finite number");
+    assert_eq!(reason("convertTo<char>(\"ab\");"), "it is not a single character");
+    assert_eq!(reason("convertTo<char>(-5);"), "it is not a valid code point");
+    assert_eq!(reason("convertTo<bool>(\"yes\");"), "only \"true\" and \"false\" convert to Bool");
+    assert_eq!(reason("convertTo<object>(5);"), "there is no conversion from Int");
+    assert_eq!(reason("convertTo<char>(true);"), "there is no conversion from Bool");
+
+    let error = run_err("convertTo(\"abc\", int);");
+    assert_eq!(error.to_string(), "Cannot convert \"abc\" to Int: it is not a whole number");
+}
+
+#[test]
+fn test_collections() {
+    assert_eq!(run("array a = [1, 2, 3]; a[0] + a[2];").unwrap(), DynamicValue::Int(4));
+    assert_eq!(run("object o = { name: \"a\", qty: 2 }; o.name + o[\"qty\"];").unwrap(), DynamicValue::from("a2"));
+    assert_eq!(run("[[1, 2], [3, 4]][1][0];").unwrap(), DynamicValue::Int(3));
+    assert_eq!(run("({ inner: { x: 5 } }).inner.x;").unwrap(), DynamicValue::Int(5));
+    assert_eq!(run("array a = [1, 2]; a.length + \"abc\".length;").unwrap(), DynamicValue::Int(5));
+    assert_eq!(run("\"héllo\"[1];").unwrap(), DynamicValue::Char('é'));
+    assert_eq!(run("[1, 2][1b];").unwrap(), DynamicValue::Int(2));
+
+    // Reading a key that is not there gives null
+    assert_eq!(run("object o = { }; o.missing;").unwrap(), DynamicValue::Null);
+}
+
+#[test]
+fn test_collection_assignment() {
+    let script = "
+        object order = { items: [{ qty: 1 }, { qty: 2 }, { qty: 3 }] };
+        order.items[2].qty += 1;
+        order.items[0].qty++;
+        order.items[1] = \"gone\";
+        order.note = \"rush\";
+        order;
+    ";
+    assert_eq!(run(script).unwrap().to_string(), "{items: [{qty: 2}, \"gone\", {qty: 4}], note: \"rush\"}");
+
+    // Indexes on the left are evaluated before the value
+    assert_eq!(run("array a = [0, 0]; int i = 0; a[i] = ++i; a;").unwrap().to_string(), "[1, 0]");
+
+    // Elements are copied out, so assigning the copy leaves the original alone
+    assert_eq!(run("array a = [1]; array b = a; b[0] = 9; a[0];").unwrap(), DynamicValue::Int(1));
+}
+
+#[test]
+fn test_typed_collections() {
+    // Elements are widened to the element type, like any other declaration
+    assert_eq!(run("float[] prices = [1, 2.5]; prices[0];").unwrap(), DynamicValue::Float(1.0));
+    assert_eq!(run("object<string, float> totals = { a: 1 }; totals.b = 2; totals;").unwrap().to_string(), "{a: 1.0, b: 2.0}");
+    assert_eq!(run("int[][] grid = [[1], [2]]; grid[1][0] += 5; grid;").unwrap().to_string(), "[[1], [7]]");
+    assert_eq!(run("convertTo<int[]>([\"1\", \"2\"]);").unwrap().to_string(), "[1, 2]");
+
+    // Every insert is checked against the element type
+    let mismatch = |expected: VarType, found: VarType| RuntimeErrorKind::TypeMismatch { expected, found };
+    assert_eq!(run_err("int[] ids = [1, \"a\"];"), mismatch(VarType::Int, VarType::String));
+    assert_eq!(run_err("int[] ids = [1]; ids[0] = 2.5;"), mismatch(VarType::Int, VarType::Float));
+    assert_eq!(run_err("object<string, bool> flags; flags.on = 1;"), mismatch(VarType::Bool, VarType::Int));
+    assert_eq!(run_err("int[][] grid = [[1]]; grid[0][0] = \"x\";"), mismatch(VarType::Int, VarType::String));
+    assert_eq!(run_err("byte[] bytes = [1]; bytes[0] += 255;"), RuntimeErrorKind::Overflow);
+    assert_eq!(run_err("fn total(float[] prices) { } total([\"free\"]);"), mismatch(VarType::Float, VarType::String));
+}
+
+#[test]
+fn test_collection_errors() {
+    assert_eq!(run_err("[1, 2][2];"), RuntimeErrorKind::IndexOutOfBounds { index: 2, length: 2 });
+    assert_eq!(run_err("[1, 2][-1];"), RuntimeErrorKind::IndexOutOfBounds { index: -1, length: 2 });
+    assert_eq!(run_err("array a; a[0] = 1;"), RuntimeErrorKind::IndexOutOfBounds { index: 0, length: 0 });
+    assert_eq!(run_err("[1][\"0\"];"), RuntimeErrorKind::InvalidOperation("Array has no member '0'".to_string()));
+    assert_eq!(run_err("[1][true];"), RuntimeErrorKind::TypeMismatch { expected: VarType::Int, found: VarType::Bool });
+    assert_eq!(run_err("({ a: 1 })[1];"), RuntimeErrorKind::TypeMismatch { expected: VarType::String, found: VarType::Int });
+    assert_eq!(run_err("int x = 1; x[0];"), RuntimeErrorKind::InvalidOperation("Cannot index Int".to_string()));
+    assert_eq!(run_err("string s = \"ab\"; s[0] = 'c';"), RuntimeErrorKind::InvalidOperation("Cannot assign to an element of String".to_string()));
+    assert_eq!(run_err("object o; o.a.b = 1;"), RuntimeErrorKind::InvalidOperation("Cannot assign below missing key 'a'".to_string()));
+
+    let error = run("array a = [1];\na[3];").err().unwrap();
+    assert_eq!(error.to_string(), "Index 3 is out of bounds for length 1 at 2:1");
+}
+
+#[test]
+fn test_arithmetic_errors() {
+    assert_eq!(run_err("1 / 0;"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(run_err("5 % 0;"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(run_err("9223372036854775807 + 1;"), RuntimeErrorKind::Overflow);
+    assert_eq!(run_err("-(-9223372036854775808);"), RuntimeErrorKind::Overflow);
+    assert_eq!(run("int x = -9223372036854775808; x;").unwrap(), DynamicValue::Int(i64::MIN));
+    assert!(matches!(run_err("true + 1;"), RuntimeErrorKind::InvalidOperation(_)));
+}
+
+#[test]
+fn test_runtime_error_points_at_expression() {
+    let error = run("int x = 1;\nint y = 2 + x / 0;").err().unwrap();
+
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error.span, Some(Span::new(23, 28, 2, 13)));
+    assert_eq!(error.to_string(), "Division by zero at 2:13");
+}
+
+#[test]
+fn test_runtime_error_inside_function_points_at_callee_body() {
+    let error = run("fn f() {\n  missing;\n}\nf();").err().unwrap();
+
+    assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable("missing".to_string()));
+    assert_eq!(error.span.map(|span| span.line), Some(2));
+}
+
+#[test]
+fn test_unicode_text() {
+    let (result, output) = run_with_output("string größe = \"grüße, \"; writeLn(größe + \"世界\"); 'é';");
+
+    assert_eq!(result.unwrap().as_char(), Some('é'));
+    assert_eq!(output, vec!["grüße, 世界"]);
+}
+
+#[test]
+fn test_interpolated_strings() {
+    let (_, output) = run_with_output("int x = 3; float price = 2.5; writeLn($\"x equals {x}, {{x}} costs {price * x:.2}\");");
+    assert_eq!(output, vec!["x equals 3, {x} costs 7.50"]);
+
+    assert_eq!(run("$\"[{7:>4}|{\"ab\":<4}|{\"ab\":^5}|{-7:04}]\";").unwrap().as_string(), Some("[   7|ab  | ab  |-007]"));
+    assert_eq!(run("$\"{255:x} {255:X} {8:o} {5:b} {\"hello\":.2}\";").unwrap().as_string(), Some("ff FF 10 101 he"));
+    assert_eq!(run("$\"{1.5} {null} {'c'} {true}\";").unwrap().as_string(), Some("1.5 null c true"));
+}
+
+#[test]
+fn test_interpolation_format_errors() {
+    let error = run("bool b = true;\n$\"flag: {b:.2}\";").err().unwrap();
+
+    assert!(matches!(error.kind, RuntimeErrorKind::InvalidOperation(_)));
+    assert_eq!(error.span, Some(Span::new(24, 25, 2, 10)));
+    assert!(matches!(run_err("$\"{1.5:x}\";"), RuntimeErrorKind::InvalidOperation(_)));
+}
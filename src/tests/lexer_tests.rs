@@ -3,12 +3,16 @@ use crate::types::var_type::VarType;
 use crate::types::tokens::Tokens;
 use crate::types::numeric::Numeric;
 use crate::types::base_functions::BaseFunctions;
+use crate::types::lex_error_kind::LexErrorKind;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::interpolation_part::InterpolationPart;
 
 #[test]
 fn test_lexer_declare_int_variable() {
     let script = "int x;";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -22,7 +26,7 @@ fn test_lexer_declare_int_variable() {
 fn test_lexer_declare_int_variable_with_value() {
     let script = "int x = 100;";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -38,7 +42,7 @@ fn test_lexer_declare_int_variable_with_value() {
 fn test_lexer_declare_float_variable() {
     let script = "float x;";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -52,7 +56,7 @@ fn test_lexer_declare_float_variable() {
 fn test_lexer_declare_float_variable_with_value() {
     let script = "float x = 100.123;";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -68,7 +72,7 @@ fn test_lexer_declare_float_variable_with_value() {
 fn test_lexer_if() {
     let script = "if (x >= 7) { int y; y = 15; }";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -93,7 +97,7 @@ fn test_lexer_if() {
 fn test_lexer_if_else() {
     let script = "if (x) { int y; } else { string z = \"this is int *=; test\"}";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -119,7 +123,7 @@ fn test_lexer_if_else() {
 fn test_while_with_spaces() {
     let script = "while (x != 10) { writeLn ($\"x equals {x}\"); x++; }";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -132,14 +136,16 @@ fn test_while_with_spaces() {
     assert_eq!(tokens[6], Tokens::LBrace);
     assert_eq!(tokens[7], Tokens::BaseFunctions(BaseFunctions::WriteLn));
     assert_eq!(tokens[8], Tokens::LParentheses);
-    assert_eq!(tokens[9], Tokens::Dollor);
-    assert_eq!(tokens[10], Tokens::StringLiteral("x equals {x}".to_string()));
-    assert_eq!(tokens[11], Tokens::RParentheses);
-    assert_eq!(tokens[12], Tokens::SemiColon);
-    assert_eq!(tokens[13], Tokens::Identifier("x".to_string()));
-    assert_eq!(tokens[14], Tokens::PlusPlus);
-    assert_eq!(tokens[15], Tokens::SemiColon);
-    assert_eq!(tokens[16], Tokens::RBrace);
+    assert_eq!(tokens[9], Tokens::InterpolatedString(vec![
+        InterpolationPart::Literal("x equals ".to_string()),
+        InterpolationPart::Hole { tokens: vec![Tokens::Identifier("x".to_string()).into()], format: None },
+    ]));
+    assert_eq!(tokens[10], Tokens::RParentheses);
+    assert_eq!(tokens[11], Tokens::SemiColon);
+    assert_eq!(tokens[12], Tokens::Identifier("x".to_string()));
+    assert_eq!(tokens[13], Tokens::PlusPlus);
+    assert_eq!(tokens[14], Tokens::SemiColon);
+    assert_eq!(tokens[15], Tokens::RBrace);
 }
 
 #[test]
@@ -147,7 +153,7 @@ fn test_while_without_spaces() {
     // This test was added due to fix an issue with empty spaces in the script
     let script = "while(x != 10){writeLn($\"x equals {x}\");x++;}";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -160,21 +166,23 @@ fn test_while_without_spaces() {
     assert_eq!(tokens[6], Tokens::LBrace);
     assert_eq!(tokens[7], Tokens::BaseFunctions(BaseFunctions::WriteLn));
     assert_eq!(tokens[8], Tokens::LParentheses);
-    assert_eq!(tokens[9], Tokens::Dollor);
-    assert_eq!(tokens[10], Tokens::StringLiteral("x equals {x}".to_string()));
-    assert_eq!(tokens[11], Tokens::RParentheses);
-    assert_eq!(tokens[12], Tokens::SemiColon);
-    assert_eq!(tokens[13], Tokens::Identifier("x".to_string()));
-    assert_eq!(tokens[14], Tokens::PlusPlus);
-    assert_eq!(tokens[15], Tokens::SemiColon);
-    assert_eq!(tokens[16], Tokens::RBrace);
+    assert_eq!(tokens[9], Tokens::InterpolatedString(vec![
+        InterpolationPart::Literal("x equals ".to_string()),
+        InterpolationPart::Hole { tokens: vec![Tokens::Identifier("x".to_string()).into()], format: None },
+    ]));
+    assert_eq!(tokens[10], Tokens::RParentheses);
+    assert_eq!(tokens[11], Tokens::SemiColon);
+    assert_eq!(tokens[12], Tokens::Identifier("x".to_string()));
+    assert_eq!(tokens[13], Tokens::PlusPlus);
+    assert_eq!(tokens[14], Tokens::SemiColon);
+    assert_eq!(tokens[15], Tokens::RBrace);
 }
 
 #[test]
 fn test_for() {
     let script = "for (int i = 0; i <= 100; i++) { writeLn ($\"i equals {i}\"); }";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -195,18 +203,20 @@ fn test_for() {
     assert_eq!(tokens[14], Tokens::LBrace);
     assert_eq!(tokens[15], Tokens::BaseFunctions(BaseFunctions::WriteLn));
     assert_eq!(tokens[16], Tokens::LParentheses);
-    assert_eq!(tokens[17], Tokens::Dollor);
-    assert_eq!(tokens[18], Tokens::StringLiteral("i equals {i}".to_string()));
-    assert_eq!(tokens[19], Tokens::RParentheses);
-    assert_eq!(tokens[20], Tokens::SemiColon);
-    assert_eq!(tokens[21], Tokens::RBrace);
+    assert_eq!(tokens[17], Tokens::InterpolatedString(vec![
+        InterpolationPart::Literal("i equals ".to_string()),
+        InterpolationPart::Hole { tokens: vec![Tokens::Identifier("i".to_string()).into()], format: None },
+    ]));
+    assert_eq!(tokens[18], Tokens::RParentheses);
+    assert_eq!(tokens[19], Tokens::SemiColon);
+    assert_eq!(tokens[20], Tokens::RBrace);
 }
 
 #[test]
 fn test_switch_case() {
     let script = "switch (x) { case 1: { k = 8; } case 2: { k-=; } }";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -239,7 +249,7 @@ fn test_switch_case() {
 fn test_function() {
     let script = "switch (x) { case 1: { k = 8; } case 2: { k-=; } }";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     println!("{:?}", tokens);
 
@@ -270,9 +280,9 @@ fn test_function() {
 
 #[test]
 fn test_base_functions() {
-    let script = "if else for while switch case fn writeLn convertTo";
+    let script = "if else for while switch case fn writeLn convertTo return break continue default try catch finally throw";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::BaseFunctions(BaseFunctions::If));
     assert_eq!(tokens[1], Tokens::BaseFunctions(BaseFunctions::Else));
@@ -283,13 +293,38 @@ fn test_base_functions() {
     assert_eq!(tokens[6], Tokens::BaseFunctions(BaseFunctions::Function));
     assert_eq!(tokens[7], Tokens::BaseFunctions(BaseFunctions::WriteLn));
     assert_eq!(tokens[8], Tokens::BaseFunctions(BaseFunctions::ConvertTo));
+    assert_eq!(tokens[9], Tokens::BaseFunctions(BaseFunctions::Return));
+    assert_eq!(tokens[10], Tokens::BaseFunctions(BaseFunctions::Break));
+    assert_eq!(tokens[11], Tokens::BaseFunctions(BaseFunctions::Continue));
+    assert_eq!(tokens[12], Tokens::BaseFunctions(BaseFunctions::Default));
+    assert_eq!(tokens[13], Tokens::BaseFunctions(BaseFunctions::Try));
+    assert_eq!(tokens[14], Tokens::BaseFunctions(BaseFunctions::Catch));
+    assert_eq!(tokens[15], Tokens::BaseFunctions(BaseFunctions::Finally));
+    assert_eq!(tokens[16], Tokens::BaseFunctions(BaseFunctions::Throw));
+}
+
+#[test]
+fn test_collection_types() {
+    let script = "array object int[] array<int> function error";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0], Tokens::BaseVariables(VarType::Array(None)));
+    // Contextual type names are left for the parser to tell apart from variables
+    assert_eq!(tokens[1], Tokens::Identifier("object".to_string()));
+    assert_eq!(tokens[3], Tokens::LSquareBracket);
+    assert_eq!(tokens[4], Tokens::RSquareBracket);
+    assert_eq!(tokens[6], Tokens::Less);
+    assert_eq!(tokens[8], Tokens::Greater);
+    assert_eq!(tokens[9], Tokens::Identifier("function".to_string()));
+    assert_eq!(tokens[10], Tokens::Identifier("error".to_string()));
 }
 
 #[test]
 fn test_numeric_literals() {
     let script = "123 45.67";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::NumberLiteral(Numeric::Int(123)));
     assert_eq!(tokens[1], Tokens::NumberLiteral(Numeric::Float(45.67)));
@@ -299,7 +334,7 @@ fn test_numeric_literals() {
 fn test_string_literal() {
     let script = "\"Hello, World!\" \"Hello, World Again!\"";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::StringLiteral("Hello, World!".to_string()));
     assert_eq!(tokens[1], Tokens::StringLiteral("Hello, World Again!".to_string()));
@@ -309,7 +344,7 @@ fn test_string_literal() {
 fn test_string_literal_with_escapes() {
     let script = r#""Line 1\nLine 2\tTabbed\\Backslash\"Quote\"\rCarriageReturn\'SingleQuote""#;
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens.len(), 1);
     assert_eq!(
@@ -322,7 +357,7 @@ fn test_string_literal_with_escapes() {
 fn test_string_literal_with_reserved_words() {
     let script = "\"fn this() { int x = 0; }\" \"Hello, World Yet Again!\"";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::StringLiteral("fn this() { int x = 0; }".to_string()));
     assert_eq!(tokens[1], Tokens::StringLiteral("Hello, World Yet Again!".to_string()));
@@ -332,7 +367,7 @@ fn test_string_literal_with_reserved_words() {
 fn test_bool_literals() {
     let script = "true false true false false true false true";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::BoolLiteral(true));
     assert_eq!(tokens[1], Tokens::BoolLiteral(false));
@@ -348,7 +383,7 @@ fn test_bool_literals() {
 fn test_char_literal() {
     let script = "'a'";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::CharLiteral('a'));
 }
@@ -357,7 +392,7 @@ fn test_char_literal() {
 fn test_base_variables() {
     let script = "int float bool char byte";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::BaseVariables(VarType::Int));
     assert_eq!(tokens[1], Tokens::BaseVariables(VarType::Float));
@@ -368,61 +403,471 @@ fn test_base_variables() {
 
 #[test]
 fn test_operators_and_punctuation() {
-    let script = ": ; . = == =+ =- =* =/ + ++ += - -- -= ! != * / /= \\ % $ @ # ^ ( ) { } [ ] , > >= < <= & && | ||";
+    let script = ": ; . = == + ++ += - -- -= ! != * *= / /= \\ % $ @ # ^ ( ) { } [ ] , > >= < <= & && | || -> =>";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::Colon);
     assert_eq!(tokens[1], Tokens::SemiColon);
     assert_eq!(tokens[2], Tokens::Period);
     assert_eq!(tokens[3], Tokens::Equals);
     assert_eq!(tokens[4], Tokens::EqualEqual);
-    assert_eq!(tokens[5], Tokens::EqualPlus);
-    assert_eq!(tokens[6], Tokens::EqualMinus);
-    assert_eq!(tokens[7], Tokens::EqualStar);
-    assert_eq!(tokens[8], Tokens::EqualForwardSlash);
-    assert_eq!(tokens[9], Tokens::Plus);
-    assert_eq!(tokens[10], Tokens::PlusPlus);
-    assert_eq!(tokens[11], Tokens::PlusEqual);
-    assert_eq!(tokens[12], Tokens::Minus);
-    assert_eq!(tokens[13], Tokens::MinusMinus);
-    assert_eq!(tokens[14], Tokens::MinusEqual);
-    assert_eq!(tokens[15], Tokens::Bang);
-    assert_eq!(tokens[16], Tokens::NotEqual);
-    assert_eq!(tokens[17], Tokens::Star);
-    assert_eq!(tokens[18], Tokens::ForwardSlash);
-    assert_eq!(tokens[19], Tokens::EqualForwardSlash);
-    assert_eq!(tokens[20], Tokens::BackSlash);
-    assert_eq!(tokens[21], Tokens::Percent);
-    assert_eq!(tokens[22], Tokens::Dollor);
-    assert_eq!(tokens[23], Tokens::At);
-    assert_eq!(tokens[24], Tokens::Hash);
-    assert_eq!(tokens[25], Tokens::Caret);
-    assert_eq!(tokens[26], Tokens::LParentheses);
-    assert_eq!(tokens[27], Tokens::RParentheses);
-    assert_eq!(tokens[28], Tokens::LBrace);
-    assert_eq!(tokens[29], Tokens::RBrace);
-    assert_eq!(tokens[30], Tokens::LSquareBracket);
-    assert_eq!(tokens[31], Tokens::RSquareBracket);
-    assert_eq!(tokens[32], Tokens::Comma);
-    assert_eq!(tokens[33], Tokens::Greater);
-    assert_eq!(tokens[34], Tokens::GreaterEqual);
-    assert_eq!(tokens[35], Tokens::Less);
-    assert_eq!(tokens[36], Tokens::LessEqual);
-    assert_eq!(tokens[37], Tokens::Ampersand);
-    assert_eq!(tokens[38], Tokens::AndAnd);
-    assert_eq!(tokens[39], Tokens::Pipe);
-    assert_eq!(tokens[40], Tokens::Or);
+    assert_eq!(tokens[5], Tokens::Plus);
+    assert_eq!(tokens[6], Tokens::PlusPlus);
+    assert_eq!(tokens[7], Tokens::PlusEqual);
+    assert_eq!(tokens[8], Tokens::Minus);
+    assert_eq!(tokens[9], Tokens::MinusMinus);
+    assert_eq!(tokens[10], Tokens::MinusEqual);
+    assert_eq!(tokens[11], Tokens::Bang);
+    assert_eq!(tokens[12], Tokens::NotEqual);
+    assert_eq!(tokens[13], Tokens::Star);
+    assert_eq!(tokens[14], Tokens::StarEqual);
+    assert_eq!(tokens[15], Tokens::ForwardSlash);
+    assert_eq!(tokens[16], Tokens::ForwardSlashEqual);
+    assert_eq!(tokens[17], Tokens::BackSlash);
+    assert_eq!(tokens[18], Tokens::Percent);
+    assert_eq!(tokens[19], Tokens::Dollor);
+    assert_eq!(tokens[20], Tokens::At);
+    assert_eq!(tokens[21], Tokens::Hash);
+    assert_eq!(tokens[22], Tokens::Caret);
+    assert_eq!(tokens[23], Tokens::LParentheses);
+    assert_eq!(tokens[24], Tokens::RParentheses);
+    assert_eq!(tokens[25], Tokens::LBrace);
+    assert_eq!(tokens[26], Tokens::RBrace);
+    assert_eq!(tokens[27], Tokens::LSquareBracket);
+    assert_eq!(tokens[28], Tokens::RSquareBracket);
+    assert_eq!(tokens[29], Tokens::Comma);
+    assert_eq!(tokens[30], Tokens::Greater);
+    assert_eq!(tokens[31], Tokens::GreaterEqual);
+    assert_eq!(tokens[32], Tokens::Less);
+    assert_eq!(tokens[33], Tokens::LessEqual);
+    assert_eq!(tokens[34], Tokens::Ampersand);
+    assert_eq!(tokens[35], Tokens::AndAnd);
+    assert_eq!(tokens[36], Tokens::Pipe);
+    assert_eq!(tokens[37], Tokens::Or);
+    assert_eq!(tokens[38], Tokens::Arrow);
+    assert_eq!(tokens[39], Tokens::FatArrow);
 }
 
 #[test]
 fn test_identifiers() {
     let script = "variableName anotherVar _privateVar var0123";
     let mut lexer = Lexer::new(script);
-    let tokens = lexer.tokenize();
+    let tokens = lexer.tokenize().unwrap();
 
     assert_eq!(tokens[0], Tokens::Identifier("variableName".to_string()));
     assert_eq!(tokens[1], Tokens::Identifier("anotherVar".to_string()));
     assert_eq!(tokens[2], Tokens::Identifier("_privateVar".to_string()));
     assert_eq!(tokens[3], Tokens::Identifier("var0123".to_string()));
 }
+
+#[test]
+fn test_token_spans() {
+    let script = "int x = 100;\n  x++;";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens[0].span, Span::new(0, 3, 1, 1));
+    assert_eq!(tokens[1].span, Span::new(4, 5, 1, 5));
+    assert_eq!(tokens[3].span, Span::new(8, 11, 1, 9));
+    assert_eq!(tokens[4].span, Span::new(11, 12, 1, 12));
+    assert_eq!(tokens[5].span, Span::new(15, 16, 2, 3));
+    assert_eq!(tokens[6].span, Span::new(16, 18, 2, 4));
+}
+
+#[test]
+fn test_token_spans_after_multiline_string() {
+    let script = "\"a\nb\" x";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0].span, Span::new(0, 5, 1, 1));
+    assert_eq!(tokens[1].span, Span::new(6, 7, 2, 4));
+}
+
+#[test]
+fn test_unknown_character() {
+    let script = "int x = 1 ~ 2;";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    println!("{:?}", errors);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::UnknownCharacter('~'));
+    assert_eq!(errors[0].span, Span::new(10, 11, 1, 11));
+}
+
+#[test]
+fn test_unterminated_string() {
+    let script = "string s = \"never closed;";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::UnterminatedString);
+    assert_eq!(errors[0].span, Span::new(11, 25, 1, 12));
+}
+
+#[test]
+fn test_invalid_escape() {
+    let script = r#""bad \q escape" x"#;
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::InvalidEscape('q'));
+}
+
+#[test]
+fn test_invalid_char_literals() {
+    let script = "'ab' '' 'c";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    println!("{:?}", errors);
+
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().all(|error| error.kind == LexErrorKind::InvalidCharLiteral));
+}
+
+#[test]
+fn test_char_literal_escapes() {
+    let script = r"'\n' '\'' '\\'";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0], Tokens::CharLiteral('\n'));
+    assert_eq!(tokens[1], Tokens::CharLiteral('\''));
+    assert_eq!(tokens[2], Tokens::CharLiteral('\\'));
+}
+
+#[test]
+fn test_number_out_of_range() {
+    let script = "int x = 99999999999999999999;";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors[0].kind, LexErrorKind::NumberOutOfRange("99999999999999999999".to_string()));
+    assert_eq!(errors[0].to_string(), "Number 99999999999999999999 is out of range at 1:9");
+
+    // One past i64::MAX is left for the parser, which accepts it only after a minus
+    let errors = Lexer::new("9223372036854775808 9_223_372_036_854_775_809").tokenize().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(Lexer::new("-9223372036854775808").tokenize().unwrap()[1], Tokens::NumberLiteral(Numeric::Int(i64::MIN)));
+}
+
+#[test]
+fn test_all_errors_are_collected() {
+    let script = "~ x ` 'ab'";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].kind, LexErrorKind::UnknownCharacter('~'));
+    assert_eq!(errors[1].kind, LexErrorKind::UnknownCharacter('`'));
+    assert_eq!(errors[2].kind, LexErrorKind::InvalidCharLiteral);
+}
+
+#[test]
+fn test_unicode_string_literals() {
+    let script = "\"héllo\" \"こんにちは 世界\" \"emoji 😀\"";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0], Tokens::StringLiteral("héllo".to_string()));
+    assert_eq!(tokens[1], Tokens::StringLiteral("こんにちは 世界".to_string()));
+    assert_eq!(tokens[2], Tokens::StringLiteral("emoji 😀".to_string()));
+}
+
+#[test]
+fn test_unicode_char_literals() {
+    let script = "'é' '世' '😀' '\\''";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0], Tokens::CharLiteral('é'));
+    assert_eq!(tokens[1], Tokens::CharLiteral('世'));
+    assert_eq!(tokens[2], Tokens::CharLiteral('😀'));
+    assert_eq!(tokens[3], Tokens::CharLiteral('\''));
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    let script = "int größe = 1; string 名前; _ñ2 = größe;";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens[1], Tokens::Identifier("größe".to_string()));
+    assert_eq!(tokens[6], Tokens::Identifier("名前".to_string()));
+    assert_eq!(tokens[8], Tokens::Identifier("_ñ2".to_string()));
+    assert_eq!(tokens[10], Tokens::Identifier("größe".to_string()));
+}
+
+#[test]
+fn test_identifiers_cannot_start_with_non_xid_characters() {
+    let script = "price€ ²x";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, LexErrorKind::UnknownCharacter('€'));
+    assert_eq!(errors[1].kind, LexErrorKind::UnknownCharacter('²'));
+}
+
+#[test]
+fn test_unicode_spans_count_characters() {
+    let script = "\"ünï\" x\u{00A0}y";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0].span, Span::new(0, 7, 1, 1));
+    assert_eq!(tokens[1].span, Span::new(8, 9, 1, 7));
+    assert_eq!(tokens[2], Tokens::Identifier("y".to_string()));
+    assert_eq!(tokens[2].span, Span::new(11, 12, 1, 9));
+}
+
+#[test]
+fn test_line_comments() {
+    let script = "int x = 1; // the answer / 2 \"quoted\"\nx /= 2; //trailing";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens.len(), 9);
+    assert_eq!(tokens[4], Tokens::SemiColon);
+    assert_eq!(tokens[5], Tokens::Identifier("x".to_string()));
+    assert_eq!(tokens[5].span.line, 2);
+}
+
+#[test]
+fn test_block_comments() {
+    let script = "int /* inline */ x /* spans\nlines */ = 1;";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens.len(), 5);
+    assert_eq!(tokens[1], Tokens::Identifier("x".to_string()));
+    assert_eq!(tokens[2], Tokens::Equals);
+    assert_eq!(tokens[2].span.line, 2);
+}
+
+#[test]
+fn test_nested_block_comments() {
+    let script = "a /* outer /* inner */ still comment */ b";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0], Tokens::Identifier("a".to_string()));
+    assert_eq!(tokens[1], Tokens::Identifier("b".to_string()));
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let script = "a /* outer /* inner */ never closed";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::UnterminatedComment);
+    assert_eq!(errors[0].span, Span::new(2, 35, 1, 3));
+}
+
+#[test]
+fn test_doc_comments() {
+    let script = "/// Adds two numbers.\n///   Second line\n//// not a doc comment\nfn";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0], Tokens::DocComment("Adds two numbers.".to_string()));
+    assert_eq!(tokens[1], Tokens::DocComment("Second line".to_string()));
+    assert_eq!(tokens[2], Tokens::BaseFunctions(BaseFunctions::Function));
+}
+
+#[test]
+fn test_comment_markers_inside_strings() {
+    let script = "\"// not a comment /* nor this */\"";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0], Tokens::StringLiteral("// not a comment /* nor this */".to_string()));
+}
+
+#[test]
+fn test_interpolated_string() {
+    let script = "$\"{{total}}: {price * 2:.2} {\"}\"}\"";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0], Tokens::InterpolatedString(vec![
+        InterpolationPart::Literal("{total}: ".to_string()),
+        InterpolationPart::Hole {
+            tokens: vec![
+                Tokens::Identifier("price".to_string()).into(),
+                Tokens::Star.into(),
+                Tokens::NumberLiteral(Numeric::Int(2)).into(),
+            ],
+            format: Some(".2".to_string()),
+        },
+        InterpolationPart::Literal(" ".to_string()),
+        InterpolationPart::Hole { tokens: vec![Tokens::StringLiteral("}".to_string()).into()], format: None },
+    ]));
+}
+
+#[test]
+fn test_interpolated_string_hole_spans() {
+    let script = "x;\n$\"a {name}\"";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    match &tokens[2].node {
+        Tokens::InterpolatedString(parts) => match &parts[1] {
+            InterpolationPart::Hole { tokens, .. } => assert_eq!(tokens[0].span, Span::new(8, 12, 2, 6)),
+            other => panic!("Expected a hole, got {:?}", other),
+        },
+        other => panic!("Expected an interpolated string, got {:?}", other),
+    }
+
+    let hole = |token: &Spanned<Tokens>| match &token.node {
+        Tokens::InterpolatedString(parts) => parts.iter().find_map(|part| match part {
+            InterpolationPart::Hole { tokens, .. } => Some(tokens.clone()),
+            InterpolationPart::Literal(_) => None,
+        }).unwrap(),
+        other => panic!("Expected an interpolated string, got {:?}", other),
+    };
+
+    // Holes spanning lines, and holes nested inside a hole's string
+    let tokens = Lexer::new("$\"{x +\n y}\"").tokenize().unwrap();
+    let inner = hole(&tokens[0]);
+    assert_eq!(inner[0].span, Span::new(3, 4, 1, 4));
+    assert_eq!(inner[2].span, Span::new(8, 9, 2, 2));
+
+    let tokens = Lexer::new("$\"a {f($\"b {c}\")}\"").tokenize().unwrap();
+    let inner = hole(&hole(&tokens[0])[2]);
+    assert_eq!(inner[0].span, Span::new(12, 13, 1, 13));
+}
+
+#[test]
+fn test_interpolated_string_errors() {
+    let errors = Lexer::new("$\"a {} b\"").tokenize().unwrap_err();
+    assert_eq!(errors[0].kind, LexErrorKind::InvalidInterpolation("empty expression".to_string()));
+
+    let errors = Lexer::new("$\"a } b\"").tokenize().unwrap_err();
+    assert_eq!(errors[0].kind, LexErrorKind::InvalidInterpolation("unmatched '}'".to_string()));
+
+    let errors = Lexer::new("$\"a {x\"").tokenize().unwrap_err();
+    assert_eq!(errors[0].kind, LexErrorKind::UnterminatedString);
+
+    // Errors inside a hole point at the offending character
+    let errors = Lexer::new("$\"a {x ~ 1}\"").tokenize().unwrap_err();
+    assert_eq!(errors[0].kind, LexErrorKind::UnknownCharacter('~'));
+    assert_eq!(errors[0].span, Span::new(7, 8, 1, 8));
+}
+
+#[test]
+fn test_radix_and_exponent_literals() {
+    let script = "0xFF 0Xff 0b1010 0o17 1_000_000 1e-9 2.5E3 1e+2 0x7FFF_FFFF_FFFF_FFFF";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens[0], Tokens::NumberLiteral(Numeric::Int(255)));
+    assert_eq!(tokens[1], Tokens::NumberLiteral(Numeric::Int(255)));
+    assert_eq!(tokens[2], Tokens::NumberLiteral(Numeric::Int(10)));
+    assert_eq!(tokens[3], Tokens::NumberLiteral(Numeric::Int(15)));
+    assert_eq!(tokens[4], Tokens::NumberLiteral(Numeric::Int(1_000_000)));
+    assert_eq!(tokens[5], Tokens::NumberLiteral(Numeric::Float(1e-9)));
+    assert_eq!(tokens[6], Tokens::NumberLiteral(Numeric::Float(2500.0)));
+    assert_eq!(tokens[7], Tokens::NumberLiteral(Numeric::Float(100.0)));
+    assert_eq!(tokens[8], Tokens::NumberLiteral(Numeric::Int(i64::MAX)));
+}
+
+#[test]
+fn test_numeric_suffixes() {
+    let script = "255b 0b 0b1010b 1.0f 3f 1e3f 7i";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    println!("{:?}", tokens);
+
+    assert_eq!(tokens[0], Tokens::NumberLiteral(Numeric::Byte(255)));
+    assert_eq!(tokens[1], Tokens::NumberLiteral(Numeric::Byte(0)));
+    assert_eq!(tokens[2], Tokens::NumberLiteral(Numeric::Byte(10)));
+    assert_eq!(tokens[3], Tokens::NumberLiteral(Numeric::Float(1.0)));
+    assert_eq!(tokens[4], Tokens::NumberLiteral(Numeric::Float(3.0)));
+    assert_eq!(tokens[5], Tokens::NumberLiteral(Numeric::Float(1000.0)));
+    assert_eq!(tokens[6], Tokens::NumberLiteral(Numeric::Int(7)));
+}
+
+#[test]
+fn test_numbers_next_to_ranges() {
+    let script = "1..10";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0], Tokens::NumberLiteral(Numeric::Int(1)));
+    assert_eq!(tokens[1], Tokens::Period);
+    assert_eq!(tokens[2], Tokens::Period);
+    assert_eq!(tokens[3], Tokens::NumberLiteral(Numeric::Int(10)));
+}
+
+#[test]
+fn test_invalid_number_literals() {
+    let script = "0x 1e 1_ 0b102 12abc 1.5b 0o8";
+    let mut lexer = Lexer::new(script);
+    let errors = lexer.tokenize().unwrap_err();
+
+    println!("{:?}", errors);
+
+    let kinds = errors.iter().map(|error| error.kind.clone()).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        LexErrorKind::InvalidNumber("0x".to_string()),
+        LexErrorKind::InvalidNumber("1e".to_string()),
+        LexErrorKind::InvalidNumber("1_".to_string()),
+        LexErrorKind::InvalidNumber("0b102".to_string()),
+        LexErrorKind::InvalidNumber("12abc".to_string()),
+        LexErrorKind::InvalidNumber("1.5b".to_string()),
+        LexErrorKind::InvalidNumber("0o8".to_string()),
+    ]);
+    assert_eq!(errors[0].to_string(), "Invalid number literal 0x at 1:1");
+}
+
+#[test]
+fn test_suffixed_number_out_of_range() {
+    let errors = Lexer::new("256b 0x1_0000_0000_0000_0000 1e400").tokenize().unwrap_err();
+
+    assert_eq!(errors[0].kind, LexErrorKind::NumberOutOfRange("256b".to_string()));
+    assert_eq!(errors[1].kind, LexErrorKind::NumberOutOfRange("0x1_0000_0000_0000_0000".to_string()));
+    assert_eq!(errors[2].kind, LexErrorKind::NumberOutOfRange("1e400".to_string()));
+}
+
+#[test]
+fn test_equals_followed_by_operator_is_not_fused() {
+    let script = "x =- 1; x =+ 1; x =* 2";
+    let mut lexer = Lexer::new(script);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[1], Tokens::Equals);
+    assert_eq!(tokens[2], Tokens::Minus);
+    assert_eq!(tokens[6], Tokens::Equals);
+    assert_eq!(tokens[7], Tokens::Plus);
+    assert_eq!(tokens[11], Tokens::Equals);
+    assert_eq!(tokens[12], Tokens::Star);
+}
@@ -1 +1,7 @@
-pub mod lexer_tests;
\ No newline at end of file
+pub mod engine_tests;
+pub mod interpreter_tests;
+pub mod lexer_tests;
+pub mod parser_tests;
+#[cfg(feature = "serde")]
+pub mod serde_tests;
+pub mod type_checker_tests;
@@ -0,0 +1,777 @@
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::types::argument::Argument;
+use crate::types::assignment_operator::AssignmentOperator;
+use crate::types::ast::Ast;
+use crate::types::ast_node::AstNode;
+use crate::types::base_functions::BaseFunctions;
+use crate::types::binary_operator::BinaryOperator;
+use crate::types::case_label::CaseLabel;
+use crate::types::expression::Expression;
+use crate::types::format_spec::FormatSpec;
+use crate::types::interpolation_segment::InterpolationSegment;
+use crate::types::numeric::Numeric;
+use crate::types::parameter::Parameter;
+use crate::types::parse_error::ParseError;
+use crate::types::signature::Signature;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::unary_operator::UnaryOperator;
+use crate::types::var_type::VarType;
+
+fn parse(script: &str) -> Result<Ast, ParseError> {
+    let mut lexer = Lexer::new(script);
+    let mut parser = Parser::new(lexer.tokenize().unwrap());
+    parser.parse()
+}
+
+fn int(value: i64) -> Spanned<Expression> {
+    Expression::Number(Numeric::Int(value)).into()
+}
+
+fn ident(name: &str) -> Spanned<Expression> {
+    Expression::Identifier(name.to_string()).into()
+}
+
+fn binary(left: Spanned<Expression>, operator: BinaryOperator, right: Spanned<Expression>) -> Spanned<Expression> {
+    chain(left, vec![(operator, right)])
+}
+
+fn chain(first: Spanned<Expression>, rest: Vec<(BinaryOperator, Spanned<Expression>)>) -> Spanned<Expression> {
+    Expression::Binary {
+        first: Box::new(first),
+        rest,
+    }.into()
+}
+
+#[test]
+fn test_parse_declarations() {
+    let ast = parse("int x; float y = 1.5;").unwrap();
+
+    println!("{:?}", ast);
+
+    assert_eq!(ast.statements.len(), 2);
+    assert_eq!(ast.statements[0], AstNode::VarDeclaration {
+        var_type: VarType::Int,
+        name: "x".to_string(),
+        value: None,
+    });
+    assert_eq!(ast.statements[1], AstNode::VarDeclaration {
+        var_type: VarType::Float,
+        name: "y".to_string(),
+        value: Some(Expression::Number(Numeric::Float(1.5)).into()),
+    });
+}
+
+#[test]
+fn test_parse_arithmetic_precedence() {
+    let ast = parse("x = 1 + 2 * 3 % 4 - 5;").unwrap();
+
+    println!("{:?}", ast);
+
+    let product = chain(int(2), vec![(BinaryOperator::Multiply, int(3)), (BinaryOperator::Modulo, int(4))]);
+    let sum = chain(int(1), vec![(BinaryOperator::Add, product), (BinaryOperator::Subtract, int(5))]);
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::Assign {
+        target: Box::new(ident("x")),
+        operator: AssignmentOperator::Assign,
+        value: Box::new(sum),
+    }.into()));
+}
+
+#[test]
+fn test_parse_logical_and_bitwise_precedence() {
+    let ast = parse("a || b && c | d ^ e & f == g >= h;").unwrap();
+
+    println!("{:?}", ast);
+
+    let comparison = binary(ident("g"), BinaryOperator::GreaterEqual, ident("h"));
+    let equality = binary(ident("f"), BinaryOperator::Equal, comparison);
+    let bit_and = binary(ident("e"), BinaryOperator::BitAnd, equality);
+    let bit_xor = binary(ident("d"), BinaryOperator::BitXor, bit_and);
+    let bit_or = binary(ident("c"), BinaryOperator::BitOr, bit_xor);
+    let and = binary(ident("b"), BinaryOperator::And, bit_or);
+    let or = binary(ident("a"), BinaryOperator::Or, and);
+    assert_eq!(ast.statements[0], AstNode::Expression(or));
+}
+
+#[test]
+fn test_parse_grouping_overrides_precedence() {
+    let ast = parse("(1 + 2) * 3;").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::Expression(
+        binary(binary(int(1), BinaryOperator::Add, int(2)), BinaryOperator::Multiply, int(3))
+    ));
+}
+
+#[test]
+fn test_parse_unary_operators() {
+    let ast = parse("!done; -x; ++i; i--;").unwrap();
+
+    println!("{:?}", ast);
+
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::Unary {
+        operator: UnaryOperator::Not,
+        operand: Box::new(ident("done")),
+    }.into()));
+    assert_eq!(ast.statements[1], AstNode::Expression(Expression::Unary {
+        operator: UnaryOperator::Negate,
+        operand: Box::new(ident("x")),
+    }.into()));
+    assert_eq!(ast.statements[2], AstNode::Expression(Expression::Unary {
+        operator: UnaryOperator::PreIncrement,
+        operand: Box::new(ident("i")),
+    }.into()));
+    assert_eq!(ast.statements[3], AstNode::Expression(Expression::Unary {
+        operator: UnaryOperator::PostDecrement,
+        operand: Box::new(ident("i")),
+    }.into()));
+}
+
+#[test]
+fn test_parse_negated_minimum_integer() {
+    let minimum = |start, end| Spanned::new(Expression::Number(Numeric::Int(i64::MIN)), Span::new(start, end, 1, start + 1));
+    let ast = parse("-9223372036854775808; x = -9223372036854775808;").unwrap();
+    assert_eq!(ast.statements[0], AstNode::Expression(minimum(0, 20)));
+    assert_eq!(ast.statements[1].node, AstNode::Expression(Spanned::new(Expression::Assign {
+        target: Box::new(ident("x")),
+        operator: AssignmentOperator::Assign,
+        value: Box::new(minimum(26, 46)),
+    }, Span::new(22, 46, 1, 23))));
+
+    assert_eq!(parse("9223372036854775808;").unwrap_err().message, "Number 9223372036854775808 is out of range");
+    assert_eq!(parse("x - 9223372036854775808;").unwrap_err().message, "Number 9223372036854775808 is out of range");
+    assert_eq!(parse("-9223372036854775808[0];").unwrap_err().message, "Number 9223372036854775808 is out of range");
+}
+
+#[test]
+fn test_parse_compound_assignment() {
+    let ast = parse("x += 1; x -= 2; x *= 3; x /= 4;").unwrap();
+
+    let operators = [
+        AssignmentOperator::AddAssign,
+        AssignmentOperator::SubtractAssign,
+        AssignmentOperator::MultiplyAssign,
+        AssignmentOperator::DivideAssign,
+    ];
+    for (statement, operator) in ast.statements.iter().zip(operators) {
+        match &statement.node {
+            AstNode::Expression(Spanned { node: Expression::Assign { operator: parsed, .. }, .. }) => assert_eq!(*parsed, operator),
+            _ => panic!("Expected an assignment, got {:?}", statement),
+        }
+    }
+}
+
+#[test]
+fn test_parse_equals_followed_by_sign() {
+    let ast = parse("x =- 1; x =+ 1;").unwrap();
+
+    for (statement, operator) in ast.statements.iter().zip([UnaryOperator::Negate, UnaryOperator::Plus]) {
+        assert_eq!(*statement, AstNode::Expression(Expression::Assign {
+            target: Box::new(ident("x")),
+            operator: AssignmentOperator::Assign,
+            value: Box::new(Expression::Unary { operator, operand: Box::new(int(1)) }.into()),
+        }.into()));
+    }
+}
+
+#[test]
+fn test_parse_reversed_compound_operators() {
+    let error = parse("x =* 2;").unwrap_err();
+    assert_eq!(error.message, "Unexpected '=*', did you mean '*='?");
+    assert_eq!(error.span, Span::new(3, 4, 1, 4));
+
+    assert_eq!(parse("x =/ 2;").unwrap_err().message, "Unexpected '=/', did you mean '/='?");
+    // With a space it is an ordinary unexpected token
+    assert_eq!(parse("x = * 2;").unwrap_err().message, "Unexpected token Star");
+}
+
+#[test]
+fn test_parse_if_else() {
+    let ast = parse("if (x >= 7) { int y; y = 15; } else { x = 0; }").unwrap();
+
+    println!("{:?}", ast);
+
+    match &ast.statements[0].node {
+        AstNode::If { condition, then_branch, else_branch } => {
+            assert_eq!(*condition, binary(ident("x"), BinaryOperator::GreaterEqual, int(7)));
+            match &then_branch.node {
+                AstNode::Block(statements) => assert_eq!(statements.len(), 2),
+                other => panic!("Expected a block, got {:?}", other),
+            }
+            assert!(else_branch.is_some());
+        },
+        other => panic!("Expected an if statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_else_if_chain() {
+    let ast = parse("if (a) { } else if (b) { } else { }").unwrap();
+
+    match &ast.statements[0].node {
+        AstNode::If { else_branch: Some(else_branch), .. } => {
+            assert!(matches!(else_branch.node, AstNode::If { else_branch: Some(_), .. }));
+        },
+        other => panic!("Expected an if statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_while() {
+    let ast = parse("while (x != 10) { writeLn(x); x++; }").unwrap();
+
+    println!("{:?}", ast);
+
+    match &ast.statements[0].node {
+        AstNode::While { condition, body } => {
+            assert_eq!(*condition, binary(ident("x"), BinaryOperator::NotEqual, int(10)));
+            match &body.node {
+                AstNode::Block(statements) => {
+                    assert_eq!(statements[0], AstNode::Expression(Expression::BaseCall {
+                        function: BaseFunctions::WriteLn,
+                        arguments: vec![ident("x")],
+                    }.into()));
+                },
+                other => panic!("Expected a block, got {:?}", other),
+            }
+        },
+        other => panic!("Expected a while loop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_for() {
+    let ast = parse("for (int i = 0; i <= 100; i++) { writeLn(i); }").unwrap();
+
+    println!("{:?}", ast);
+
+    match &ast.statements[0].node {
+        AstNode::For { initializer, condition, update, .. } => {
+            assert_eq!(initializer.as_deref(), Some(&AstNode::VarDeclaration {
+                var_type: VarType::Int,
+                name: "i".to_string(),
+                value: Some(int(0)),
+            }.into()));
+            assert_eq!(*condition, Some(binary(ident("i"), BinaryOperator::LessEqual, int(100))));
+            assert_eq!(*update, Some(Expression::Unary {
+                operator: UnaryOperator::PostIncrement,
+                operand: Box::new(ident("i")),
+            }.into()));
+        },
+        other => panic!("Expected a for loop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_for_with_empty_clauses() {
+    let ast = parse("for (;;) { }").unwrap();
+
+    assert!(matches!(
+        &ast.statements[0].node,
+        AstNode::For { initializer: None, condition: None, update: None, .. }
+    ));
+}
+
+#[test]
+fn test_parse_switch_case() {
+    let ast = parse("switch (x) { case 1: { k = 8; } case 2: k = 9; k++; }").unwrap();
+
+    println!("{:?}", ast);
+
+    match &ast.statements[0].node {
+        AstNode::Switch { subject, cases, default } => {
+            assert_eq!(*subject, ident("x"));
+            assert_eq!(cases.len(), 2);
+            assert_eq!(cases[0].labels, vec![CaseLabel::Value(int(1))]);
+            assert_eq!(cases[0].body.len(), 1);
+            assert_eq!(cases[1].labels, vec![CaseLabel::Value(int(2))]);
+            assert_eq!(cases[1].body.len(), 2);
+            assert_eq!(*default, None);
+        },
+        other => panic!("Expected a switch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_switch_default() {
+    let ast = parse("switch (x) { default: k = 0; case 1: k = 1; }").unwrap();
+
+    match &ast.statements[0].node {
+        AstNode::Switch { cases, default, .. } => {
+            assert_eq!(cases.len(), 1);
+            assert_eq!(default.as_ref().map(Vec::len), Some(1));
+        },
+        other => panic!("Expected a switch, got {:?}", other),
+    }
+
+    let error = parse("switch (x) { default: k = 0; default: k = 1; }").unwrap_err();
+    assert_eq!(error.message, "Duplicate 'default' in switch");
+    assert_eq!(parse("switch (x) { k = 0; }").unwrap_err().message, "Expected 'case' or 'default' in switch body");
+}
+
+#[test]
+fn test_parse_case_labels() {
+    let ast = parse("switch (x) { case 1, 2..5, 'a'..'z', y.len: k = 0; }").unwrap();
+
+    match &ast.statements[0].node {
+        AstNode::Switch { cases, .. } => assert_eq!(cases[0].labels, vec![
+            CaseLabel::Value(int(1)),
+            CaseLabel::Range { start: int(2), end: int(5) },
+            CaseLabel::Range {
+                start: Expression::Char('a').into(),
+                end: Expression::Char('z').into(),
+            },
+            CaseLabel::Value(Expression::Member { target: Box::new(ident("y")), name: "len".to_string() }.into()),
+        ]),
+        other => panic!("Expected a switch, got {:?}", other),
+    }
+
+    // A range needs its periods side by side
+    assert_eq!(parse("switch (x) { case 1. .5: k = 0; }").unwrap_err().message, "Expected member name after '.'");
+    assert_eq!(parse("switch (x) { case 1,: k = 0; }").unwrap_err().message, "Unexpected token Colon");
+    assert_eq!(parse("switch (x) { case 1..: k = 0; }").unwrap_err().message, "Unexpected token Colon");
+}
+
+#[test]
+fn test_parse_jumps() {
+    let ast = parse("fn f() { while (true) { break; continue; } return; return 1; }").unwrap();
+
+    match &ast.statements[0].node {
+        AstNode::Function { body, .. } => {
+            match &body[0].node {
+                AstNode::While { body, .. } => assert_eq!(body.node, AstNode::Block(vec![
+                    AstNode::Break.into(),
+                    AstNode::Continue.into(),
+                ])),
+                other => panic!("Expected a loop, got {:?}", other),
+            }
+            assert_eq!(body[1].node, AstNode::Return(None));
+            assert_eq!(body[2].node, AstNode::Return(Some(int(1))));
+        },
+        other => panic!("Expected a function, got {:?}", other),
+    }
+
+    // break also leaves a switch, even outside a loop
+    assert!(parse("switch (x) { case 1: break; }").is_ok());
+    assert!(parse("for (;;) { switch (x) { case 1: continue; } }").is_ok());
+}
+
+#[test]
+fn test_parse_misplaced_jumps() {
+    assert_eq!(parse("break;").unwrap_err().message, "'break' outside of a loop or switch");
+    assert_eq!(parse("if (x) { continue; }").unwrap_err().message, "'continue' outside of a loop");
+    assert_eq!(parse("switch (x) { case 1: continue; }").unwrap_err().message, "'continue' outside of a loop");
+    assert_eq!(parse("return 1;").unwrap_err().message, "'return' outside of a function");
+
+    // A function body does not inherit the loop around it
+    let error = parse("while (true) { fn f() { break; } }").unwrap_err();
+    assert_eq!(error.message, "'break' outside of a loop or switch");
+    assert_eq!(error.span, Span::new(24, 29, 1, 25));
+    assert_eq!(parse("fn f() { } return;").unwrap_err().message, "'return' outside of a function");
+    assert_eq!(parse("while (true) { break }").unwrap_err().message, "Expected ';' after 'break'");
+}
+
+#[test]
+fn test_parse_function_definition_and_call() {
+    let ast = parse("fn add(int a, b) { writeLn(a + b); } add(1, 2);").unwrap();
+
+    println!("{:?}", ast);
+
+    match &ast.statements[0].node {
+        AstNode::Function { doc, name, signature, body } => {
+            assert_eq!(*doc, None);
+            assert_eq!(name, "add");
+            assert_eq!(signature.parameters, vec![
+                Parameter { var_type: Some(VarType::Int), name: "a".to_string(), default: None },
+                Parameter { var_type: None, name: "b".to_string(), default: None },
+            ]);
+            assert_eq!(signature.return_type, None);
+            assert_eq!(body.len(), 1);
+        },
+        other => panic!("Expected a function, got {:?}", other),
+    }
+    assert_eq!(ast.statements[1], AstNode::Expression(Expression::Call {
+        name: "add".to_string(),
+        arguments: vec![
+            Argument { name: None, value: int(1) },
+            Argument { name: None, value: int(2) },
+        ],
+    }.into()));
+}
+
+#[test]
+fn test_parse_function_signature() {
+    let ast = parse("fn add(int a, int b = 1, c = a) -> int { return a + b; } add(1, c: 3, b: 2);").unwrap();
+
+    match &ast.statements[0].node {
+        AstNode::Function { signature, .. } => assert_eq!(*signature, Signature {
+            parameters: vec![
+                Parameter { var_type: Some(VarType::Int), name: "a".to_string(), default: None },
+                Parameter { var_type: Some(VarType::Int), name: "b".to_string(), default: Some(int(1)) },
+                Parameter { var_type: None, name: "c".to_string(), default: Some(ident("a")) },
+            ],
+            return_type: Some(VarType::Int),
+        }),
+        other => panic!("Expected a function, got {:?}", other),
+    }
+    assert_eq!(ast.statements[1], AstNode::Expression(Expression::Call {
+        name: "add".to_string(),
+        arguments: vec![
+            Argument { name: None, value: int(1) },
+            Argument { name: Some("c".to_string()), value: int(3) },
+            Argument { name: Some("b".to_string()), value: int(2) },
+        ],
+    }.into()));
+
+    let ast = parse("fn pairs() -> array<int[]> { }").unwrap();
+    match &ast.statements[0].node {
+        AstNode::Function { signature, .. } => {
+            assert_eq!(signature.return_type, Some(VarType::array_of(VarType::array_of(VarType::Int))));
+        },
+        other => panic!("Expected a function, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_function_signature_errors() {
+    assert_eq!(parse("fn f(int a = 1, int b) { }").unwrap_err().message, "Parameter 'b' needs a default, as it follows one that has one");
+    assert_eq!(parse("fn f(a, a) { }").unwrap_err().message, "Duplicate parameter 'a'");
+    assert_eq!(parse("fn f() -> { }").unwrap_err().message, "Expected return type after '->'");
+    assert_eq!(parse("f(a: 1, 2);").unwrap_err().message, "Positional arguments must come before named ones");
+}
+
+#[test]
+fn test_parse_try() {
+    let ast = parse("try { risky(); } catch (e) { throw e; } finally { done(); } try { } finally { }").unwrap();
+
+    let call = |name: &str| AstNode::Expression(Expression::Call { name: name.to_string(), arguments: vec![] }.into());
+    match &ast.statements[0].node {
+        AstNode::Try { body, catch: Some((name, handler)), finally: Some(finally) } => {
+            assert_eq!(body[0].node, call("risky"));
+            assert_eq!(name, "e");
+            assert_eq!(handler[0].node, AstNode::Throw(ident("e")));
+            assert_eq!(finally[0].node, call("done"));
+        },
+        other => panic!("Expected a try statement, got {:?}", other),
+    }
+    assert_eq!(ast.statements[1].node, AstNode::Try { body: vec![], catch: None, finally: Some(vec![]) });
+
+    assert_eq!(parse("try { }").unwrap_err().message, "Expected 'catch' or 'finally' after 'try' block");
+    assert_eq!(parse("try { } catch { }").unwrap_err().message, "Expected '(' after 'catch'");
+    assert_eq!(parse("try { } catch (1) { }").unwrap_err().message, "Expected a name for the caught error");
+    assert_eq!(parse("throw \"bad\"").unwrap_err().message, "Expected ';' after thrown value");
+}
+
+#[test]
+fn test_parse_lambdas() {
+    let ast = parse("function double = fn(int x) => x * 2; fn(a, b) -> int { return a; }; handlers[0](1)(name: 2);").unwrap();
+
+    let lambda = |index: usize| match &ast.statements[index].node {
+        AstNode::VarDeclaration { value: Some(value), .. } | AstNode::Expression(value) => match &value.node {
+            Expression::Lambda(function) => function.clone(),
+            other => panic!("Expected a lambda, got {:?}", other),
+        },
+        other => panic!("Expected a lambda, got {:?}", other),
+    };
+
+    // An expression body becomes a single return
+    let double = lambda(0);
+    assert_eq!(double.name, "lambda");
+    assert_eq!(double.signature.parameters, vec![Parameter { var_type: Some(VarType::Int), name: "x".to_string(), default: None }]);
+    let product = binary(ident("x"), BinaryOperator::Multiply, int(2));
+    assert_eq!(double.body[0].node, AstNode::Return(Some(product)));
+
+    let first = lambda(1);
+    assert_eq!(first.signature.return_type, Some(VarType::Int));
+    assert_eq!(first.body[0].node, AstNode::Return(Some(ident("a"))));
+
+    let handler = Expression::Index { target: Box::new(ident("handlers")), index: Box::new(int(0)) };
+    let call = Expression::Invoke { target: Box::new(handler.into()), arguments: vec![Argument { name: None, value: int(1) }] };
+    assert_eq!(ast.statements[2], AstNode::Expression(Expression::Invoke {
+        target: Box::new(call.into()),
+        arguments: vec![Argument { name: Some("name".to_string()), value: int(2) }],
+    }.into()));
+
+    assert_eq!(parse("f = fn(x) x;").unwrap_err().message, "Expected '=>' or a block after lambda parameters");
+    assert_eq!(parse("while (true) { f = fn() { break; }; }").unwrap_err().message, "'break' outside of a loop or switch");
+}
+
+#[test]
+fn test_parse_convert_to() {
+    let ast = parse("string s = convertTo(42, string);").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::VarDeclaration {
+        var_type: VarType::String,
+        name: "s".to_string(),
+        value: Some(Expression::ConvertTo {
+            value: Box::new(int(42)),
+            target: VarType::String,
+        }.into()),
+    });
+}
+
+#[test]
+fn test_parse_convert_to_generic() {
+    let ast = parse("convertTo<int>(42);").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::ConvertTo {
+        value: Box::new(int(42)),
+        target: VarType::Int,
+    }.into()));
+
+    let error = parse("convertTo<int(42);").unwrap_err();
+    assert_eq!(error.message, "Expected '>' after target type");
+}
+
+#[test]
+fn test_parse_literals() {
+    let ast = parse("\"text\"; 'c'; true; null;").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::StringLiteral("text".to_string()).into()));
+    assert_eq!(ast.statements[1], AstNode::Expression(Expression::Char('c').into()));
+    assert_eq!(ast.statements[2], AstNode::Expression(Expression::Bool(true).into()));
+    assert_eq!(ast.statements[3], AstNode::Expression(Expression::Null.into()));
+}
+
+#[test]
+fn test_parse_missing_semicolon() {
+    let error = parse("int x = 1").unwrap_err();
+
+    assert_eq!(error.message, "Expected ';' after variable declaration");
+    assert_eq!(error.span, Span::new(9, 9, 1, 10));
+}
+
+#[test]
+fn test_parse_unclosed_block() {
+    let error = parse("while (true) { x++;").unwrap_err();
+
+    assert_eq!(error.message, "Expected '}' to close block");
+}
+
+#[test]
+fn test_parse_invalid_assignment_target() {
+    let error = parse("1 = 2;").unwrap_err();
+
+    assert_eq!(error.message, "Invalid assignment target");
+
+    let error = parse("items()[0] = 2;").unwrap_err();
+    assert_eq!(error.message, "Invalid assignment target");
+
+    let error = parse("[1][0]++;").unwrap_err();
+    assert_eq!(error.message, "Increment and decrement need a variable");
+}
+
+#[test]
+fn test_parse_collection_literals() {
+    let ast = parse("[1, 2,]; ({ name: \"a\", \"qty\": 2 }); [];").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::Array(vec![int(1), int(2)]).into()));
+    assert_eq!(ast.statements[1], AstNode::Expression(Expression::Object(vec![
+        ("name".to_string(), Expression::StringLiteral("a".to_string()).into()),
+        ("qty".to_string(), int(2)),
+    ]).into()));
+    assert_eq!(ast.statements[2], AstNode::Expression(Expression::Array(Vec::new()).into()));
+
+    // A brace starting a statement is still a block
+    let ast = parse("{ 1; }").unwrap();
+    assert_eq!(ast.statements[0], AstNode::Block(vec![AstNode::Expression(int(1)).into()]));
+}
+
+#[test]
+fn test_parse_collection_errors() {
+    assert_eq!(parse("[1 2];").unwrap_err().message, "Expected ',' or ']' in array literal");
+    assert_eq!(parse("x = { a: 1 b: 2 };").unwrap_err().message, "Expected ',' or '}' in object literal");
+    assert_eq!(parse("x = { 1: 2 };").unwrap_err().message, "Expected a key in object literal");
+    assert_eq!(parse("x = { a: 1, a: 2 };").unwrap_err().message, "Duplicate key 'a' in object literal");
+    assert_eq!(parse("x.1;").unwrap_err().message, "Expected member name after '.'");
+    assert_eq!(parse("x[1;").unwrap_err().message, "Expected ']' after index");
+}
+
+#[test]
+fn test_parse_collection_types() {
+    let ast = parse("array<int> a; float[] b; object<string, float> c; int[][] d; array<object> e; convertTo<string[]>(x);").unwrap();
+    let declared = |index: usize| match &ast.statements[index].node {
+        AstNode::VarDeclaration { var_type, .. } => var_type.clone(),
+        other => panic!("expected a declaration, found {:?}", other),
+    };
+
+    assert_eq!(declared(0), VarType::array_of(VarType::Int));
+    assert_eq!(declared(1), VarType::array_of(VarType::Float));
+    assert_eq!(declared(2), VarType::object_of(VarType::Float));
+    assert_eq!(declared(3), VarType::array_of(VarType::array_of(VarType::Int)));
+    assert_eq!(declared(4), VarType::array_of(VarType::Object(None)));
+    assert_eq!(format!("{:?}", declared(3)), "Array<Array<Int>>");
+    assert_eq!(ast.statements[5], AstNode::Expression(Expression::ConvertTo {
+        value: Box::new(ident("x")),
+        target: VarType::array_of(VarType::String),
+    }.into()));
+
+    assert_eq!(parse("object<int, float> c;").unwrap_err().message, "Object keys are always strings");
+    assert_eq!(parse("array<int c;").unwrap_err().message, "Expected '>' after array element type");
+}
+
+#[test]
+fn test_parse_nested_assignment() {
+    let ast = parse("order.items[2].qty += 1;").unwrap();
+
+    let items = Expression::Member { target: Box::new(ident("order")), name: "items".to_string() };
+    let item = Expression::Index { target: Box::new(items.into()), index: Box::new(int(2)) };
+    let qty = Expression::Member { target: Box::new(item.into()), name: "qty".to_string() };
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::Assign {
+        target: Box::new(qty.into()),
+        operator: AssignmentOperator::AddAssign,
+        value: Box::new(int(1)),
+    }.into()));
+
+    // Postfix operators bind tighter than prefix ones
+    let ast = parse("-a[0];").unwrap();
+    assert_eq!(ast.statements[0], AstNode::Expression(Expression::Unary {
+        operator: UnaryOperator::Negate,
+        operand: Box::new(Expression::Index { target: Box::new(ident("a")), index: Box::new(int(0)) }.into()),
+    }.into()));
+}
+
+#[test]
+fn test_parse_invalid_increment_target() {
+    assert!(parse("5++;").is_err());
+    assert!(parse("++5;").is_err());
+}
+
+#[test]
+fn test_parse_error_points_at_token() {
+    let error = parse("int x = 1;\nx = = 2;").unwrap_err();
+
+    assert_eq!(error.message, "Unexpected token Equals");
+    assert_eq!(error.span, Span::new(15, 16, 2, 5));
+    assert_eq!(error.to_string(), "Unexpected token Equals at 2:5");
+}
+
+#[test]
+fn test_parse_node_spans() {
+    let ast = parse("int x = 1;\nx = x + 20;").unwrap();
+
+    assert_eq!(ast.statements[0].span, Span::new(0, 10, 1, 1));
+    assert_eq!(ast.statements[1].span, Span::new(11, 22, 2, 1));
+    match &ast.statements[1].node {
+        AstNode::Expression(Spanned { node: Expression::Assign { value, .. }, span }) => {
+            assert_eq!(*span, Span::new(11, 21, 2, 1));
+            assert_eq!(value.span, Span::new(15, 21, 2, 5));
+        },
+        other => panic!("Expected an assignment, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_doc_comments_attach_to_functions() {
+    let ast = parse("/// Doubles a value.\n/// Returns nothing yet.\nfn double(int x) { } /// stray\nint y;").unwrap();
+
+    println!("{:?}", ast);
+
+    assert_eq!(ast.statements.len(), 2);
+    match &ast.statements[0].node {
+        AstNode::Function { doc, name, .. } => {
+            assert_eq!(name, "double");
+            assert_eq!(doc.as_deref(), Some("Doubles a value.\nReturns nothing yet."));
+        },
+        other => panic!("Expected a function, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_ignores_doc_comments_inside_expressions() {
+    let ast = parse("int x = /// note\n 1;").unwrap();
+
+    assert_eq!(ast.statements[0], AstNode::VarDeclaration {
+        var_type: VarType::Int,
+        name: "x".to_string(),
+        value: Some(int(1)),
+    });
+
+    // Lambdas take no doc comment, so one above them is ignored too
+    let ast = parse("/// Doubles a value.\nfunction f = fn(x) => x * 2;\n/// Unused.\nfn(x) => x;").unwrap();
+    assert!(matches!(&ast.statements[0].node, AstNode::VarDeclaration { var_type: VarType::Function, .. }));
+    assert!(matches!(&ast.statements[1].node, AstNode::Expression(lambda) if matches!(lambda.node, Expression::Lambda(_))));
+}
+
+#[test]
+fn test_parse_interpolated_string() {
+    let ast = parse("$\"sum {a + 1:>6.2} of {a}\";").unwrap();
+
+    println!("{:?}", ast);
+
+    assert_eq!(ast.statements[0], AstNode::Expression(Spanned::from(Expression::Interpolated(vec![
+        InterpolationSegment::Literal("sum ".to_string()),
+        InterpolationSegment::Hole {
+            expression: binary(ident("a"), BinaryOperator::Add, int(1)),
+            format: Some(FormatSpec { align: Some('>'), width: Some(6), precision: Some(2), ..FormatSpec::default() }),
+        },
+        InterpolationSegment::Literal(" of ".to_string()),
+        InterpolationSegment::Hole { expression: ident("a"), format: None },
+    ]))));
+}
+
+#[test]
+fn test_parse_interpolation_errors() {
+    assert_eq!(parse("$\"{a b}\";").unwrap_err().message, "Expected '}' after interpolated expression");
+    assert_eq!(parse("$\"{a:.}\";").unwrap_err().message, "Invalid format specifier '.'");
+    assert_eq!(parse("$\"{a:q}\";").unwrap_err().message, "Invalid format specifier 'q'");
+    assert_eq!(parse("$\"{a:.4000000000}\";").unwrap_err().message, "Invalid format specifier '.4000000000'");
+    assert_eq!(parse("$\"{a:99999999999999}\";").unwrap_err().message, "Invalid format specifier '99999999999999'");
+    assert_eq!(parse("$\"{a:1025}\";").unwrap_err().message, "Invalid format specifier '1025'");
+    assert!(parse("$\"{a:>1024.1024}\";").is_ok());
+}
+
+#[test]
+fn test_parse_nesting_limit() {
+    let nested = |open: &str, inner: &str, close: &str, levels: usize| {
+        format!("x = {}{}{};", open.repeat(levels), inner, close.repeat(levels))
+    };
+    assert!(parse(&nested("(", "1", ")", 50)).is_ok());
+
+    // A flat chain nests one level however long it is
+    let ast = parse(&format!("x = 1{};", " + 1".repeat(5000))).unwrap();
+    let AstNode::Expression(assignment) = &ast.statements[0].node else { panic!("Expected an expression") };
+    let Expression::Assign { value, .. } = &assignment.node else { panic!("Expected an assignment") };
+    assert!(matches!(&value.node, Expression::Binary { rest, .. } if rest.len() == 5000));
+
+    let error = parse(&nested("(", "1", ")", 500)).unwrap_err();
+    assert_eq!(error.message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&nested("[", "1", "]", 5000)).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&nested("-", "1", "", 5000)).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&format!("x{};", "[0]".repeat(5000))).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&format!("{}{}", "{".repeat(5000), "}".repeat(5000))).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&format!("x {};", "= x".repeat(5000))).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&format!("{}int{} x;", "array<".repeat(5000), ">".repeat(5000))).unwrap_err().message, "Nested more than 64 levels deep");
+    assert_eq!(parse(&format!("int{} x;", "[]".repeat(5000))).unwrap_err().message, "Nested more than 64 levels deep");
+}
+
+#[test]
+fn test_parse_contextual_type_names() {
+    let declared = |script: &str| match parse(script).unwrap().statements[0].node.clone() {
+        AstNode::VarDeclaration { var_type, .. } => var_type,
+        other => panic!("Expected a declaration, got {:?}", other),
+    };
+    assert_eq!(declared("object o;"), VarType::Object(None));
+    assert_eq!(declared("object[] list;"), VarType::array_of(VarType::Object(None)));
+    assert_eq!(declared("object<string, int> counts;"), VarType::object_of(VarType::Int));
+    assert_eq!(declared("array<object> list;"), VarType::array_of(VarType::Object(None)));
+    assert_eq!(declared("function f = fn() => 1;"), VarType::Function);
+    assert_eq!(declared("function[] handlers;"), VarType::array_of(VarType::Function));
+    assert_eq!(declared("error last;"), VarType::Error);
+
+    // Anywhere else they are ordinary names
+    let ast = parse("int object = 1; object + 1; object[0]; object < 2;").unwrap();
+    assert_eq!(ast.statements[1], AstNode::Expression(binary(ident("object"), BinaryOperator::Add, int(1))));
+    assert!(matches!(&ast.statements[2].node, AstNode::Expression(index) if matches!(index.node, Expression::Index { .. })));
+    let ast = parse("fn function() { } function(); string s = convertTo<string>(function);").unwrap();
+    assert!(matches!(&ast.statements[1].node, AstNode::Expression(call) if matches!(&call.node, Expression::Call { name, .. } if name == "function")));
+    let ast = parse("string error = \"\"; try { } catch (error) { error.message; }").unwrap();
+    assert!(matches!(&ast.statements[0].node, AstNode::VarDeclaration { name, var_type: VarType::String, .. } if name == "error"));
+
+    let ast = parse("fn f(object o, object) -> object { return o; }").unwrap();
+    let AstNode::Function { signature, .. } = &ast.statements[0].node else { panic!("Expected a function") };
+    assert_eq!(signature.parameters[0].var_type, Some(VarType::Object(None)));
+    assert_eq!(signature.parameters[1].var_type, None);
+    assert_eq!(signature.parameters[1].name, "object");
+    assert_eq!(signature.return_type, Some(VarType::Object(None)));
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::{from_dynamic, to_dynamic, DynamicValue, Engine, RuntimeErrorKind, Scope};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Invoice {
+    customer: String,
+    total: f64,
+    quantity: i32,
+    paid: bool,
+    discount: Option<f64>,
+    status: Status,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Status {
+    Open,
+    Refunded { amount: f64 },
+}
+
+fn invoice() -> Invoice {
+    Invoice {
+        customer: "acme".to_string(),
+        total: 120.5,
+        quantity: 3,
+        paid: false,
+        discount: None,
+        status: Status::Open,
+    }
+}
+
+#[test]
+fn test_struct_round_trip() {
+    let value = to_dynamic(&invoice()).unwrap();
+    println!("{}", value);
+
+    let fields = value.as_object().unwrap();
+    assert_eq!(fields["customer"], DynamicValue::from("acme"));
+    assert_eq!(fields["quantity"], DynamicValue::Int(3));
+    assert_eq!(fields["discount"], DynamicValue::Null);
+    assert_eq!(fields["status"], DynamicValue::from("Open"));
+
+    assert_eq!(from_dynamic::<Invoice>(value).unwrap(), invoice());
+
+    let refunded = Invoice { status: Status::Refunded { amount: 20.0 }, discount: Some(0.5), ..invoice() };
+    let value = to_dynamic(&refunded).unwrap();
+    assert_eq!(value.as_object().unwrap()["status"].to_string(), "{Refunded: {amount: 20.0}}");
+    assert_eq!(from_dynamic::<Invoice>(value).unwrap(), refunded);
+}
+
+#[test]
+fn test_structs_as_script_globals() {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+    scope.set("quantity", to_dynamic(&invoice().quantity).unwrap());
+    scope.set("invoice", to_dynamic(&invoice()).unwrap());
+
+    let result = engine.eval("quantity * 2;", &mut scope).unwrap();
+    assert_eq!(from_dynamic::<i64>(result).unwrap(), 6);
+
+    let invoice = from_dynamic::<Invoice>(scope.get("invoice").unwrap().clone()).unwrap();
+    assert_eq!(invoice.customer, "acme");
+}
+
+#[test]
+fn test_json_round_trip() {
+    let json = r#"{"name":"widget","price":2.5,"stock":[1,2,null],"active":true}"#;
+    let value = serde_json::from_str::<DynamicValue>(json).unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert("name".to_string(), DynamicValue::from("widget"));
+    expected.insert("price".to_string(), DynamicValue::Float(2.5));
+    expected.insert("stock".to_string(), DynamicValue::Array(vec![DynamicValue::Int(1), DynamicValue::Int(2), DynamicValue::Null]));
+    expected.insert("active".to_string(), DynamicValue::Bool(true));
+    assert_eq!(value, DynamicValue::Object(expected));
+
+    // Keys come out sorted, so the output is stable
+    assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"active":true,"name":"widget","price":2.5,"stock":[1,2,null]}"#);
+}
+
+#[test]
+fn test_serde_errors() {
+    let error = from_dynamic::<Invoice>(DynamicValue::Int(1)).unwrap_err();
+    println!("{}", error);
+    assert!(matches!(error.kind, RuntimeErrorKind::Serialization(_)));
+
+    let error = from_dynamic::<u8>(DynamicValue::Int(300)).unwrap_err();
+    assert!(matches!(error.kind, RuntimeErrorKind::Serialization(_)));
+
+    assert!(to_dynamic(&u64::MAX).is_err());
+    assert!(to_dynamic(&HashMap::from([(vec![1], 2)])).is_err());
+
+    // Functions only exist inside scripts
+    let engine = Engine::new();
+    let script = engine.compile("fn(x) => x;").unwrap();
+    let function = engine.run(&script, &mut Scope::new()).unwrap();
+    let error = from_dynamic::<serde_json::Value>(function).unwrap_err();
+    assert!(matches!(error.kind, RuntimeErrorKind::Serialization(_)));
+}
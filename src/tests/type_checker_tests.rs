@@ -0,0 +1,259 @@
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::type_checker::TypeChecker;
+use crate::types::span::Span;
+use crate::types::type_error::TypeError;
+use crate::{DynamicValue, Engine, Error, Scope};
+
+fn check(script: &str) -> Result<(), Vec<TypeError>> {
+    let tokens = Lexer::new(script).tokenize().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    TypeChecker::new().check(&ast)
+}
+
+fn messages(script: &str) -> Vec<String> {
+    let errors = check(script).unwrap_err();
+    println!("{:?}", errors);
+    errors.into_iter().map(|error| error.message).collect()
+}
+
+#[test]
+fn test_well_typed_script() {
+    let script = "
+        int total = 0;
+        float average = 1;
+        byte small = 200;
+        for (int i = 0; i < 10; i++) {
+            total += i * 2;
+        }
+        fn scale(int value, factor) {
+            value * factor;
+        }
+        string label = $\"{total} items\";
+        average = total / 3.0;
+        scale(total, 2.5);
+        bool done = total > 10 && !false;
+    ";
+    assert_eq!(check(script), Ok(()));
+}
+
+#[test]
+fn test_declaration_mismatch() {
+    let errors = check("int x = \"hello\";").unwrap_err();
+    println!("{:?}", errors);
+    assert_eq!(errors, vec![TypeError::new("Type mismatch: expected Int, found String", Span::new(8, 15, 1, 9))]);
+}
+
+#[test]
+fn test_assignment_mismatch() {
+    assert_eq!(messages("string s; s = 1;"), vec!["Type mismatch: expected String, found Int"]);
+    assert_eq!(messages("int x; x += 1.5;"), vec!["Type mismatch: expected Int, found Float"]);
+}
+
+#[test]
+fn test_arithmetic_on_bool() {
+    assert_eq!(messages("bool b = true; b + 1;"), vec!["Cannot apply Add to Bool and Int"]);
+    assert_eq!(messages("-true;"), vec!["Cannot negate Bool"]);
+    assert_eq!(messages("!1;"), vec!["Type mismatch: expected Bool, found Int"]);
+}
+
+#[test]
+fn test_conditions_must_be_bool() {
+    assert_eq!(messages("if (1) { }"), vec!["Type mismatch: expected Bool, found Int"]);
+    assert_eq!(messages("while (\"yes\") { }"), vec!["Type mismatch: expected Bool, found String"]);
+}
+
+#[test]
+fn test_undeclared_function() {
+    let errors = check("int x = 1;\nmissing(x);").unwrap_err();
+    println!("{:?}", errors);
+    assert_eq!(errors, vec![TypeError::new("Undefined function 'missing'", Span::new(11, 21, 2, 1))]);
+}
+
+#[test]
+fn test_function_arguments() {
+    assert_eq!(messages("fn add(int a, int b) { a + b; } add(1);"), vec!["'add' expects 2 argument(s), got 1"]);
+    assert_eq!(messages("fn add(int a, int b) { a + b; } add(1, \"2\");"), vec!["Type mismatch: expected Int, found String"]);
+
+    // Functions are hoisted, so calling one before its declaration is fine
+    assert_eq!(check("twice(2); fn twice(int n) { n * 2; }"), Ok(()));
+}
+
+#[test]
+fn test_function_signatures() {
+    let function = "fn add(int a, int b = 1) -> int { return a + b; }";
+    let checked = |call: &str| check(&format!("{} {}", function, call));
+    let reported = |call: &str| messages(&format!("{} {}", function, call));
+
+    assert_eq!(checked("int x = add(1); x = add(b: 2, a: 3) + add(1, 2);"), Ok(()));
+    assert_eq!(reported("add();"), vec!["'add' expects 1 argument(s), got 0"]);
+    assert_eq!(reported("add(1, 2, 3);"), vec!["'add' expects 2 argument(s), got 3"]);
+    assert_eq!(reported("add(b: 1);"), vec!["'add' is missing argument 'a'"]);
+    assert_eq!(reported("add(1, c: 1);"), vec!["'add' has no parameter 'c'"]);
+    assert_eq!(reported("add(1, a: 1);"), vec!["Argument 'a' is passed to 'add' more than once"]);
+    assert_eq!(reported("add(1, b: true);"), vec!["Type mismatch: expected Int, found Bool"]);
+
+    // Calls have the declared return type
+    assert_eq!(reported("string s = add(1);"), vec!["Type mismatch: expected String, found Int"]);
+    assert_eq!(reported("add(1) && true;"), vec!["Type mismatch: expected Bool, found Int"]);
+}
+
+#[test]
+fn test_return_and_default_types() {
+    assert_eq!(messages("fn name() -> string { if (true) { return 1; } return \"a\"; }"), vec!["Type mismatch: expected String, found Int"]);
+    assert_eq!(messages("fn f(int a = \"x\") { }"), vec!["Type mismatch: expected Int, found String"]);
+
+    // Defaults see earlier parameters, and nested functions have their own return type
+    assert_eq!(check("fn f(int a, int b = a + 1) -> int { fn g() -> string { return \"x\"; } return b; }"), Ok(()));
+    assert_eq!(check("fn f() { return 1; } fn g() { return; } fn h() -> null { return; }"), Ok(()));
+}
+
+#[test]
+fn test_missing_return_values() {
+    assert_eq!(messages("fn f() -> int { }"), vec!["Function can end without returning a value of type Int"]);
+    assert_eq!(messages("fn f() -> int { return; }"), vec!["Expected a return value of type Int"]);
+    assert_eq!(messages("fn f(int x) -> int { if (x > 0) { return 1; } }"), vec!["Function can end without returning a value of type Int"]);
+    assert_eq!(messages("fn f() -> int { while (true) { break; } }"), vec!["Function can end without returning a value of type Int"]);
+    assert_eq!(messages("function f = fn() -> string { };"), vec!["Function can end without returning a value of type String"]);
+
+    // Every path that ends the function returns or throws
+    assert_eq!(check("fn f(int x) -> int { if (x > 0) { return 1; } else { throw \"negative\"; } }"), Ok(()));
+    assert_eq!(check("fn f(int x) -> int { switch (x) { case 1: { return 1; } default: { return 0; } } }"), Ok(()));
+    assert_eq!(check("fn f() -> int { while (true) { for (;;) { break; } return 1; } }"), Ok(()));
+    assert_eq!(check("fn f() -> int { try { return 1; } catch (e) { return 0; } }"), Ok(()));
+    assert_eq!(check("function f = fn(int x) -> int => x;"), Ok(()));
+}
+
+#[test]
+fn test_function_bodies() {
+    // Bodies see their parameters and the globals, but not the caller's locals
+    assert_eq!(messages("string name; fn greet(int n) { n + true; name - 1; }"), vec![
+        "Cannot apply Add to Int and Bool",
+        "Cannot apply Subtract to String and Int",
+    ]);
+}
+
+#[test]
+fn test_duplicate_functions() {
+    assert_eq!(messages("fn f() { } fn f() { }"), vec!["'f' is already declared"]);
+    assert_eq!(messages("fn f() { } if (true) { fn f(int x) { } }"), vec!["'f' is already declared"]);
+
+    // Functions declared inside lambda bodies are collected too
+    assert_eq!(check("function f = fn() { fn helper() { return 1; } return helper(); }; f();"), Ok(()));
+    assert_eq!(messages("fn g() { } array handlers = [fn() { fn g() { } }];"), vec!["'g' is already declared"]);
+
+    let tokens = Lexer::new("fn log(string line) { }").tokenize().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let mut checker = TypeChecker::new();
+    checker.register_native("log", None);
+    let errors = checker.check(&ast).unwrap_err();
+    assert_eq!(errors, vec![TypeError::new("'log' is already declared", Span::new(0, 23, 1, 1))]);
+}
+
+#[test]
+fn test_collections() {
+    assert_eq!(check("array a = [1]; object o = { a: a[0] }; int n = a.length; char c = \"ab\"[0]; o.a.b;"), Ok(()));
+    assert_eq!(messages("array a; a[\"x\"];"), vec!["Type mismatch: expected Int, found String"]);
+    assert_eq!(messages("object o; o[1];"), vec!["Type mismatch: expected String, found Int"]);
+    assert_eq!(messages("int x; x[0]; x.size;"), vec!["Cannot index Int", "Int has no member 'size'"]);
+    assert_eq!(messages("int n = [1, 2];"), vec!["Type mismatch: expected Int, found Array<Int>"]);
+}
+
+#[test]
+fn test_typed_collections() {
+    assert_eq!(check("float[] prices = [1, 2.5]; float first = prices[0]; object<string, int> stock = { a: 1 }; stock.a += 1;"), Ok(()));
+    assert_eq!(messages("array<int> ids = [\"a\", \"b\"];"), vec!["Type mismatch: expected Array<Int>, found Array<String>"]);
+    assert_eq!(messages("int[] ids; ids[0] = \"a\";"), vec!["Type mismatch: expected Int, found String"]);
+    assert_eq!(messages("object<string, bool> flags; flags.on = 1;"), vec!["Type mismatch: expected Bool, found Int"]);
+    assert_eq!(messages("int[][] grid; string cell = grid[0][0];"), vec!["Type mismatch: expected String, found Int"]);
+
+    // Untyped collections and mixed literals are checked when the script runs
+    assert_eq!(check("array any; int[] ids = any; int[] mixed = [1, \"a\"];"), Ok(()));
+}
+
+#[test]
+fn test_switch_labels() {
+    assert_eq!(check("int n = 3; switch (n) { case 1, 2: n = 0; case 3..5, 7: n = 1; case 6: n = 2; default: n = 3; }"), Ok(()));
+
+    assert_eq!(messages("switch (1) { case 1: case 2, 1: }"), vec!["Duplicate case label 1"]);
+    assert_eq!(messages("switch ('c') { case 'a'..'z': case 'a'..'z': }"), vec!["Duplicate case label 'a'..'z'"]);
+    assert_eq!(messages("switch (\"a\") { case \"a\", \"b\": case \"b\": }"), vec!["Duplicate case label \"b\""]);
+    assert_eq!(messages("switch (x) { case 1..10: case 5: case -1..3: case 2..4: }"), vec![
+        "Case label 5 is unreachable; 1..10 already covers it",
+        "Case label 2..4 is unreachable; 1..10 already covers it",
+    ]);
+    assert_eq!(messages("switch (x) { case 1: case 1.0: }"), vec!["Case label 1.0 is unreachable; 1 already covers it"]);
+    assert_eq!(messages("switch (x) { case 10..1: }"), vec!["Case range 10..1 is empty"]);
+
+    // Labels of the wrong type can never match
+    assert_eq!(messages("int n = 1; switch (n) { case \"1\": case 'a'..'c': }"), vec![
+        "Case label of type String never matches a subject of type Int",
+        "Case label of type Char never matches a subject of type Int",
+    ]);
+    assert_eq!(messages("switch (x) { case 1..'z': }"), vec!["Case range bounds must both be numbers or both be chars"]);
+
+    // Labels only known at run time are never reported
+    assert_eq!(check("switch (x) { case y: case y: case 1..z: case 5: }"), Ok(()));
+}
+
+#[test]
+fn test_lambdas() {
+    assert_eq!(check("function f = fn(int x) -> int => x * 2; int y = f(1); fn twice(g) { return g(g(1)); } twice(f); [f][0](2);"), Ok(()));
+
+    // Lambda bodies see the locals they capture and are checked against their own return type
+    assert_eq!(messages("{ string s = \"a\"; function f = fn() -> int => s; }"), vec!["Type mismatch: expected Int, found String"]);
+    assert_eq!(messages("fn outer() -> string { function f = fn() -> int => 1; return 2; }"), vec!["Type mismatch: expected String, found Int"]);
+
+    assert_eq!(messages("int x = 1; x(1);"), vec!["'x' is not a function"]);
+    assert_eq!(messages("\"text\"(1);"), vec!["Cannot call String"]);
+    assert_eq!(messages("function f = 1;"), vec!["Type mismatch: expected Function, found Int"]);
+    assert_eq!(messages("fn g() { } int n = g;"), vec!["Type mismatch: expected Int, found Function"]);
+    assert_eq!(messages("string[] a = [1] + [2];"), vec!["Type mismatch: expected Array<String>, found Array<Int>"]);
+    assert_eq!(check("int[] a = [1] + [\"b\"];"), Ok(()));
+}
+
+#[test]
+fn test_try_catch() {
+    let script = "error saved; try { } catch (e) { string m = e.message + e.kind; int at = e.line + e.column; string[] s = e.stack; saved = e; } finally { } throw saved;";
+    assert_eq!(check(script), Ok(()));
+
+    assert_eq!(messages("try { } catch (e) { int m = e.message; }"), vec!["Type mismatch: expected Int, found String"]);
+    assert_eq!(messages("try { } catch (e) { e.code; }"), vec!["Error has no member 'code'"]);
+    assert_eq!(messages("throw 1;"), vec!["Cannot throw Int"]);
+    assert_eq!(messages("error e = \"text\";"), vec!["Type mismatch: expected Error, found String"]);
+}
+
+#[test]
+fn test_all_errors_are_reported() {
+    let errors = check("int x = true;\nbool b = 1;\nundefined();").unwrap_err();
+    println!("{:?}", errors);
+    let lines = errors.iter().map(|error| error.span.line).collect::<Vec<_>>();
+    assert_eq!(lines, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_unknown_types_are_not_reported() {
+    // Host globals, untyped parameters and call results are only known at run time
+    assert_eq!(check("total + 1; fn f(a) { a - 1; } f(1) * 2;"), Ok(()));
+}
+
+#[test]
+fn test_engine_reports_type_errors() {
+    let mut engine = Engine::new();
+    let error = engine.compile("int x = \"hello\";").unwrap_err();
+    assert!(matches!(&error, Error::Type(errors) if errors.len() == 1));
+    assert_eq!(error.to_string(), "Type mismatch: expected Int, found String at 1:9");
+
+    // Nothing runs when the check fails
+    let mut scope = Scope::new();
+    assert!(engine.eval("int y = 1; bool b = y;", &mut scope).is_err());
+    assert!(!scope.contains("y"));
+
+    engine.register_typed_fn("double", |x: i64| x * 2);
+    assert_eq!(engine.eval("double(4);", &mut scope).unwrap().as_int(), Some(8));
+    assert_eq!(engine.eval("x = 1; x;", &mut Scope::new()).err().unwrap().to_string(), "Undefined variable 'x' at 1:1");
+
+    let mut scope = Scope::new();
+    scope.set("x", DynamicValue::Int(1));
+    assert!(engine.eval("x = 1; x;", &mut scope).is_ok());
+}
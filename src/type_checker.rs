@@ -0,0 +1,789 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::interpreter::compound_operator;
+use crate::types::ast::Ast;
+use crate::types::ast_node::AstNode;
+use crate::types::binary_operator::BinaryOperator;
+use crate::types::case_label::CaseLabel;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::expression::Expression;
+use crate::types::interpolation_segment::InterpolationSegment;
+use crate::types::numeric::Numeric;
+use crate::types::signature::Signature;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+use crate::types::switch_case::SwitchCase;
+use crate::types::type_error::TypeError;
+use crate::types::unary_operator::UnaryOperator;
+use crate::types::var_type::VarType;
+
+// None means the type is only known at run time: host globals, untyped parameters and call results
+type StaticType = Option<VarType>;
+
+// Mirrors the interpreter's typing rules so that anything reported here would fail at run time
+pub struct TypeChecker {
+    scopes: Vec<HashMap<String, StaticType>>, // scopes[0] holds the globals
+    globals: HashMap<String, StaticType>,     // Every top-level declaration, as seen from function bodies
+    functions: HashMap<String, Signature>,
+    natives: HashMap<String, Option<Signature>>, // None for host functions that check their own arguments
+    return_type: Option<VarType>,                // Declared by the function being checked
+    errors: Vec<TypeError>,
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        TypeChecker {
+            scopes: vec![HashMap::new()],
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            natives: HashMap::new(),
+            return_type: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn register_native(&mut self, name: &str, signature: Option<Signature>) {
+        self.natives.insert(name.to_string(), signature);
+    }
+
+    // Checks a whole program, reporting every problem rather than stopping at the first
+    pub fn check(&mut self, ast: &Ast) -> Result<(), Vec<TypeError>> {
+        self.collect_declarations(&ast.statements);
+        for statement in &ast.statements {
+            self.check_statement(statement);
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // Functions are hoisted and may run after any top-level declaration, so gather both up front
+    fn collect_declarations(&mut self, statements: &[Spanned<AstNode>]) {
+        for statement in statements {
+            if let AstNode::VarDeclaration { var_type, name, .. } = &statement.node {
+                self.globals.insert(name.clone(), Some(var_type.clone()));
+            }
+        }
+        self.collect_functions(statements);
+    }
+
+    fn collect_functions(&mut self, statements: &[Spanned<AstNode>]) {
+        for statement in statements {
+            match &statement.node {
+                // Functions share one namespace wherever they are declared, and scripts may not replace host functions
+                AstNode::Function { name, signature, body, .. } => {
+                    if self.functions.contains_key(name) || self.natives.contains_key(name) {
+                        self.error(&format!("'{}' is already declared", name), statement.span);
+                    } else {
+                        self.functions.insert(name.clone(), signature.clone());
+                    }
+                    self.collect_signature_functions(signature);
+                    self.collect_functions(body);
+                },
+                AstNode::Block(statements) => self.collect_functions(statements),
+                AstNode::If { condition, then_branch, else_branch } => {
+                    self.collect_expression_functions(condition);
+                    self.collect_functions(std::slice::from_ref(then_branch));
+                    if let Some(else_branch) = else_branch {
+                        self.collect_functions(std::slice::from_ref(else_branch));
+                    }
+                },
+                AstNode::While { condition, body } => {
+                    self.collect_expression_functions(condition);
+                    self.collect_functions(std::slice::from_ref(body));
+                },
+                AstNode::For { initializer, condition, update, body } => {
+                    if let Some(initializer) = initializer {
+                        self.collect_functions(std::slice::from_ref(initializer));
+                    }
+                    for expression in condition.iter().chain(update) {
+                        self.collect_expression_functions(expression);
+                    }
+                    self.collect_functions(std::slice::from_ref(body));
+                },
+                AstNode::Switch { subject, cases, default } => {
+                    self.collect_expression_functions(subject);
+                    for case in cases {
+                        for label in &case.labels {
+                            match label {
+                                CaseLabel::Value(value) => self.collect_expression_functions(value),
+                                CaseLabel::Range { start, end } => {
+                                    self.collect_expression_functions(start);
+                                    self.collect_expression_functions(end);
+                                },
+                            }
+                        }
+                        self.collect_functions(&case.body);
+                    }
+                    if let Some(default) = default {
+                        self.collect_functions(default);
+                    }
+                },
+                AstNode::Try { body, catch, finally } => {
+                    self.collect_functions(body);
+                    if let Some((_, handler)) = catch {
+                        self.collect_functions(handler);
+                    }
+                    if let Some(finally) = finally {
+                        self.collect_functions(finally);
+                    }
+                },
+                AstNode::VarDeclaration { value: Some(expression), .. }
+                | AstNode::Expression(expression)
+                | AstNode::Return(Some(expression))
+                | AstNode::Throw(expression) => self.collect_expression_functions(expression),
+                AstNode::VarDeclaration { value: None, .. }
+                | AstNode::Return(None)
+                | AstNode::Break
+                | AstNode::Continue => {},
+            }
+        }
+    }
+
+    // Lambda bodies can declare functions too, and so can the lambdas in parameter defaults
+    fn collect_signature_functions(&mut self, signature: &Signature) {
+        for default in signature.parameters.iter().filter_map(|parameter| parameter.default.as_ref()) {
+            self.collect_expression_functions(default);
+        }
+    }
+
+    fn collect_expression_functions(&mut self, expression: &Spanned<Expression>) {
+        match &expression.node {
+            Expression::Lambda(function) => {
+                self.collect_signature_functions(&function.signature);
+                self.collect_functions(&function.body);
+            },
+            Expression::Interpolated(segments) => {
+                for segment in segments {
+                    if let InterpolationSegment::Hole { expression, .. } = segment {
+                        self.collect_expression_functions(expression);
+                    }
+                }
+            },
+            Expression::Array(elements) | Expression::BaseCall { arguments: elements, .. } => {
+                for element in elements {
+                    self.collect_expression_functions(element);
+                }
+            },
+            Expression::Object(entries) => {
+                for (_, value) in entries {
+                    self.collect_expression_functions(value);
+                }
+            },
+            Expression::Binary { first, rest } => {
+                self.collect_expression_functions(first);
+                for (_, operand) in rest {
+                    self.collect_expression_functions(operand);
+                }
+            },
+            Expression::Index { target: left, index: right } | Expression::Assign { target: left, value: right, .. } => {
+                self.collect_expression_functions(left);
+                self.collect_expression_functions(right);
+            },
+            Expression::Member { target, .. }
+            | Expression::Unary { operand: target, .. }
+            | Expression::ConvertTo { value: target, .. } => self.collect_expression_functions(target),
+            Expression::Call { arguments, .. } => {
+                for argument in arguments {
+                    self.collect_expression_functions(&argument.value);
+                }
+            },
+            Expression::Invoke { target, arguments } => {
+                self.collect_expression_functions(target);
+                for argument in arguments {
+                    self.collect_expression_functions(&argument.value);
+                }
+            },
+            Expression::Number(_)
+            | Expression::StringLiteral(_)
+            | Expression::Bool(_)
+            | Expression::Char(_)
+            | Expression::Null
+            | Expression::Identifier(_) => {},
+        }
+    }
+
+    // Statements
+
+    fn check_statement(&mut self, statement: &Spanned<AstNode>) {
+        match &statement.node {
+            AstNode::VarDeclaration { var_type, name, value } => {
+                if let Some(value) = value {
+                    let found = self.check_expression(value);
+                    self.expect_assignable(var_type, &found, value.span);
+                }
+                let scope = self.scopes.last_mut().expect("global scope always exists");
+                if scope.insert(name.clone(), Some(var_type.clone())).is_some() {
+                    self.error(&format!("'{}' is already declared", name), statement.span);
+                }
+            },
+            AstNode::Expression(expression) => {
+                self.check_expression(expression);
+            },
+            AstNode::Block(statements) => self.check_block(statements),
+            AstNode::If { condition, then_branch, else_branch } => {
+                self.check_condition(condition);
+                self.check_scoped(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_scoped(else_branch);
+                }
+            },
+            AstNode::While { condition, body } => {
+                self.check_condition(condition);
+                self.check_scoped(body);
+            },
+            AstNode::For { initializer, condition, update, body } => {
+                self.scopes.push(HashMap::new());
+                if let Some(initializer) = initializer {
+                    self.check_statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.check_condition(condition);
+                }
+                if let Some(update) = update {
+                    self.check_expression(update);
+                }
+                self.check_scoped(body);
+                self.scopes.pop();
+            },
+            AstNode::Switch { subject, cases, default } => {
+                let subject = self.check_expression(subject);
+                self.check_cases(&subject, cases);
+                if let Some(default) = default {
+                    self.check_block(default);
+                }
+            },
+            AstNode::Function { signature, body, .. } => {
+                // Function bodies see the globals and their own parameters, never the enclosing locals
+                self.check_function(signature, body, vec![self.globals.clone()], statement.span);
+            },
+            AstNode::Return(value) => match (value, self.return_type.clone()) {
+                (Some(value), return_type) => {
+                    let found = self.check_expression(value);
+                    if let Some(return_type) = return_type {
+                        self.expect_assignable(&return_type, &found, value.span);
+                    }
+                },
+                (None, Some(return_type)) if return_type != VarType::Null => {
+                    self.error(&format!("Expected a return value of type {:?}", return_type), statement.span);
+                },
+                (None, _) => {},
+            },
+            AstNode::Break | AstNode::Continue => {},
+            AstNode::Try { body, catch, finally } => {
+                self.check_block(body);
+                if let Some((name, handler)) = catch {
+                    self.scopes.push(HashMap::from([(name.clone(), Some(VarType::Error))]));
+                    self.check_block(handler);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = finally {
+                    self.check_block(finally);
+                }
+            },
+            // Only a message or a caught error can be thrown
+            AstNode::Throw(value) => match self.check_expression(value) {
+                Some(VarType::String | VarType::Error) | None => {},
+                Some(other) => self.error(&format!("Cannot throw {:?}", other), value.span),
+            },
+        }
+    }
+
+    // Reports labels that can never match: the wrong type, an empty range, or a constant an earlier label already covers
+    fn check_cases(&mut self, subject: &StaticType, cases: &[SwitchCase]) {
+        let mut covered: Vec<(DynamicValue, DynamicValue, String)> = Vec::new();
+        for case in cases {
+            for label in &case.labels {
+                let (found, bounds) = match label {
+                    CaseLabel::Value(value) => {
+                        let found = self.check_expression(value);
+                        (found, constant(&value.node).map(|value| (value.clone(), value)))
+                    },
+                    CaseLabel::Range { start, end } => {
+                        let found = match (self.check_expression(start), self.check_expression(end)) {
+                            (Some(VarType::Char), Some(VarType::Char)) => Some(VarType::Char),
+                            (Some(low), Some(high)) if is_numeric(&low) && is_numeric(&high) => Some(VarType::Float),
+                            (Some(_), Some(_)) => self.invalid("Case range bounds must both be numbers or both be chars", label.span()),
+                            _ => None,
+                        };
+                        (found, constant(&start.node).zip(constant(&end.node)))
+                    },
+                };
+                if let (Some(subject), Some(found)) = (subject, &found) {
+                    if !assignable(subject, found) && !assignable(found, subject) {
+                        let message = format!("Case label of type {:?} never matches a subject of type {:?}", found, subject);
+                        self.error(&message, label.span());
+                        continue;
+                    }
+                }
+                let Some((low, high)) = bounds else { continue };
+                let text = match label {
+                    CaseLabel::Value(_) => describe(&low),
+                    CaseLabel::Range { .. } => format!("{}..{}", describe(&low), describe(&high)),
+                };
+                if label_order(&low, &high) == Some(Ordering::Greater) {
+                    self.error(&format!("Case range {} is empty", text), label.span());
+                    continue;
+                }
+                let earlier = covered.iter().find(|(start, end, _)| {
+                    matches!(label_order(start, &low), Some(Ordering::Less | Ordering::Equal))
+                        && matches!(label_order(&high, end), Some(Ordering::Less | Ordering::Equal))
+                });
+                match earlier {
+                    Some((_, _, earlier)) if *earlier == text => {
+                        self.error(&format!("Duplicate case label {}", text), label.span());
+                    },
+                    Some((_, _, earlier)) => {
+                        let message = format!("Case label {} is unreachable; {} already covers it", text, earlier);
+                        self.error(&message, label.span());
+                    },
+                    None => covered.push((low, high, text)),
+                }
+            }
+            self.check_block(&case.body);
+        }
+    }
+
+    // Checks a body in a new frame on top of `scopes`, which are put back afterwards
+    fn check_function(&mut self, signature: &Signature, body: &[Spanned<AstNode>], mut scopes: Vec<HashMap<String, StaticType>>, span: Span) {
+        scopes.push(HashMap::new());
+        let enclosing = std::mem::replace(&mut self.scopes, scopes);
+        let enclosing_return = std::mem::replace(&mut self.return_type, signature.return_type.clone());
+        for parameter in &signature.parameters {
+            // Defaults run in the new frame, after the parameters before them
+            if let Some(default) = &parameter.default {
+                let found = self.check_expression(default);
+                if let Some(var_type) = &parameter.var_type {
+                    self.expect_assignable(var_type, &found, default.span);
+                }
+            }
+            let frame = self.scopes.last_mut().expect("function frame was just pushed");
+            frame.insert(parameter.name.clone(), parameter.var_type.clone());
+        }
+        for statement in body {
+            self.check_statement(statement);
+        }
+        // Falling off the end would return null, which only a null return type allows
+        if let Some(return_type) = signature.return_type.as_ref().filter(|return_type| **return_type != VarType::Null) {
+            if !always_returns(body) {
+                self.error(&format!("Function can end without returning a value of type {:?}", return_type), span);
+            }
+        }
+        self.scopes = enclosing;
+        self.return_type = enclosing_return;
+    }
+
+    // Checks a chain link by link from the left, each link spanning everything before it
+    fn check_binary(&mut self, first: &Spanned<Expression>, rest: &[(BinaryOperator, Spanned<Expression>)]) -> StaticType {
+        let mut found = self.check_expression(first);
+        let mut found_span = first.span;
+        for (operator, right) in rest {
+            let span = first.span.to(right.span);
+            found = match operator {
+                BinaryOperator::And | BinaryOperator::Or => {
+                    self.expect_type(&VarType::Bool, &found, found_span);
+                    self.check_condition(right);
+                    Some(VarType::Bool)
+                },
+                _ => {
+                    let right = self.check_expression(right);
+                    self.binary_type(&found, operator, &right, span)
+                },
+            };
+            found_span = span;
+        }
+        found
+    }
+
+    fn check_block(&mut self, statements: &[Spanned<AstNode>]) {
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            self.check_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_scoped(&mut self, node: &Spanned<AstNode>) {
+        match &node.node {
+            AstNode::Block(statements) => self.check_block(statements),
+            _ => self.check_block(std::slice::from_ref(node)),
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Spanned<Expression>) {
+        let found = self.check_expression(condition);
+        self.expect_type(&VarType::Bool, &found, condition.span);
+    }
+
+    // Expressions
+
+    fn check_expression(&mut self, expression: &Spanned<Expression>) -> StaticType {
+        let span = expression.span;
+        match &expression.node {
+            Expression::Number(Numeric::Int(_)) => Some(VarType::Int),
+            Expression::Number(Numeric::Float(_)) => Some(VarType::Float),
+            Expression::Number(Numeric::Byte(_)) => Some(VarType::Byte),
+            Expression::StringLiteral(_) => Some(VarType::String),
+            Expression::Interpolated(segments) => {
+                for segment in segments {
+                    if let InterpolationSegment::Hole { expression, .. } = segment {
+                        self.check_expression(expression);
+                    }
+                }
+                Some(VarType::String)
+            },
+            Expression::Bool(_) => Some(VarType::Bool),
+            Expression::Char(_) => Some(VarType::Char),
+            Expression::Null => Some(VarType::Null),
+            // A named function can be used as a value
+            Expression::Identifier(name) if !self.is_declared(name) && self.functions.contains_key(name) => Some(VarType::Function),
+            Expression::Identifier(name) => self.lookup(name),
+            // A literal whose elements all have the same known type is typed by them
+            Expression::Array(elements) => {
+                let found = elements.iter().map(|element| self.check_expression(element)).collect::<Vec<_>>();
+                Some(VarType::Array(common_type(found)))
+            },
+            Expression::Object(entries) => {
+                let found = entries.iter().map(|(_, value)| self.check_expression(value)).collect::<Vec<_>>();
+                Some(VarType::Object(common_type(found)))
+            },
+            Expression::Index { target, index } => {
+                let target = self.check_expression(target);
+                let found = self.check_expression(index);
+                match target {
+                    Some(VarType::Array(element)) => {
+                        self.expect_assignable(&VarType::Int, &found, index.span);
+                        element.map(|element| *element)
+                    },
+                    Some(VarType::String) => {
+                        self.expect_assignable(&VarType::Int, &found, index.span);
+                        Some(VarType::Char)
+                    },
+                    Some(VarType::Object(element)) => {
+                        self.expect_type(&VarType::String, &found, index.span);
+                        element.map(|element| *element)
+                    },
+                    Some(other) => self.invalid(&format!("Cannot index {:?}", other), span),
+                    None => None,
+                }
+            },
+            Expression::Member { target, name } => match self.check_expression(target) {
+                Some(VarType::Array(_) | VarType::String) if name == "length" => Some(VarType::Int),
+                Some(VarType::Object(element)) => element.map(|element| *element),
+                Some(VarType::Error) => match name.as_str() {
+                    "message" | "kind" => Some(VarType::String),
+                    "line" | "column" => Some(VarType::Int),
+                    "stack" => Some(VarType::array_of(VarType::String)),
+                    _ => self.invalid(&format!("Error has no member '{}'", name), span),
+                },
+                None => None,
+                Some(other) => self.invalid(&format!("{:?} has no member '{}'", other, name), span),
+            },
+            Expression::Unary { operator, operand } => self.check_unary(operator, operand, span),
+            Expression::Binary { first, rest } => self.check_binary(first, rest),
+            Expression::Assign { target, operator, value } => {
+                let value_type = self.check_expression(value);
+                let target_type = self.check_expression(target);
+                let result = match compound_operator(operator) {
+                    Some(operator) => self.binary_type(&target_type, &operator, &value_type, span),
+                    None => value_type,
+                };
+                match &target_type {
+                    Some(declared) => {
+                        self.expect_assignable(declared, &result, value.span);
+                        target_type
+                    },
+                    None => result,
+                }
+            },
+            Expression::Call { name, arguments } => {
+                let found = arguments.iter().map(|argument| self.check_expression(&argument.value)).collect::<Vec<_>>();
+                // A variable holding a function comes first, and which one it holds is only known at run time
+                let variable = self.is_declared(name).then(|| self.lookup(name));
+                if let Some(None | Some(VarType::Function)) = variable {
+                    return None;
+                }
+                let signature = match (self.functions.get(name), self.natives.get(name)) {
+                    (Some(signature), _) | (None, Some(Some(signature))) => signature.clone(),
+                    (None, Some(None)) => return None,
+                    (None, None) if variable.is_some() => return self.invalid(&format!("'{}' is not a function", name), span),
+                    (None, None) => return self.invalid(&format!("Undefined function '{}'", name), span),
+                };
+                let names = arguments.iter().map(|argument| argument.name.as_deref()).collect::<Vec<_>>();
+                match signature.bind(name, &names) {
+                    Ok(slots) => {
+                        for (parameter, slot) in signature.parameters.iter().zip(slots) {
+                            if let (Some(expected), Some(index)) = (&parameter.var_type, slot) {
+                                self.expect_assignable(expected, &found[index], arguments[index].value.span);
+                            }
+                        }
+                    },
+                    Err(error) => self.error(&error.to_string(), span),
+                }
+                signature.return_type
+            },
+            Expression::Invoke { target, arguments } => {
+                let found = self.check_expression(target);
+                for argument in arguments {
+                    self.check_expression(&argument.value);
+                }
+                match found {
+                    Some(VarType::Function) | None => None,
+                    Some(other) => self.invalid(&format!("Cannot call {:?}", other), span),
+                }
+            },
+            // Lambdas also see the locals around them, which they capture
+            Expression::Lambda(function) => {
+                self.check_function(&function.signature, &function.body, self.scopes.clone(), span);
+                Some(VarType::Function)
+            },
+            Expression::BaseCall { arguments, .. } => {
+                for argument in arguments {
+                    self.check_expression(argument);
+                }
+                Some(VarType::Null)
+            },
+            Expression::ConvertTo { value, target } => {
+                self.check_expression(value);
+                Some(target.clone())
+            },
+        }
+    }
+
+    fn check_unary(&mut self, operator: &UnaryOperator, operand: &Spanned<Expression>, span: Span) -> StaticType {
+        let found = self.check_expression(operand)?;
+        match operator {
+            UnaryOperator::Not => {
+                self.expect_type(&VarType::Bool, &Some(found), operand.span);
+                Some(VarType::Bool)
+            },
+            UnaryOperator::Negate => match found {
+                VarType::Int | VarType::Byte => Some(VarType::Int),
+                VarType::Float => Some(VarType::Float),
+                other => self.invalid(&format!("Cannot negate {:?}", other), span),
+            },
+            UnaryOperator::Plus => match found {
+                VarType::Int | VarType::Float | VarType::Byte => Some(found),
+                other => self.invalid(&format!("Cannot apply unary '+' to {:?}", other), span),
+            },
+            _ => match found {
+                VarType::Int | VarType::Float | VarType::Byte => Some(found),
+                other => self.invalid(&format!("Cannot increment or decrement {:?}", other), span),
+            },
+        }
+    }
+
+    fn binary_type(&mut self, left: &StaticType, operator: &BinaryOperator, right: &StaticType, span: Span) -> StaticType {
+        if matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual) {
+            return Some(VarType::Bool);
+        }
+        if *operator == BinaryOperator::Add && (*left == Some(VarType::String) || *right == Some(VarType::String)) {
+            return Some(VarType::String);
+        }
+        // Joining arrays keeps the element type only when both sides share it
+        if let (BinaryOperator::Add, Some(VarType::Array(a)), Some(VarType::Array(b))) = (operator, left, right) {
+            return Some(VarType::Array(if a == b { a.clone() } else { None }));
+        }
+        let comparison = matches!(
+            operator,
+            BinaryOperator::Less | BinaryOperator::LessEqual | BinaryOperator::Greater | BinaryOperator::GreaterEqual
+        );
+        let (left, right) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            _ if comparison => return Some(VarType::Bool),
+            _ => return None,
+        };
+
+        let result = match operator {
+            _ if comparison => match (left, right) {
+                (VarType::String, VarType::String) | (VarType::Char, VarType::Char) => Some(VarType::Bool),
+                _ if is_numeric(left) && is_numeric(right) => Some(VarType::Bool),
+                _ => None,
+            },
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => match (left, right) {
+                (VarType::Bool, VarType::Bool) => Some(VarType::Bool),
+                (VarType::Byte, VarType::Byte) => Some(VarType::Byte),
+                _ if is_integer(left) && is_integer(right) => Some(VarType::Int),
+                _ => None,
+            },
+            _ => match (left, right) {
+                (VarType::Byte, VarType::Byte) => Some(VarType::Byte),
+                _ if is_integer(left) && is_integer(right) => Some(VarType::Int),
+                _ if is_numeric(left) && is_numeric(right) => Some(VarType::Float),
+                _ => None,
+            },
+        };
+        match result {
+            Some(result) => Some(result),
+            None => self.invalid(&format!("Cannot apply {:?} to {:?} and {:?}", operator, left, right), span),
+        }
+    }
+
+    // Helpers
+
+    fn lookup(&self, name: &str) -> StaticType {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned().flatten()
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains_key(name))
+    }
+
+    fn expect_type(&mut self, expected: &VarType, found: &StaticType, span: Span) {
+        if let Some(found) = found {
+            if found != expected {
+                self.mismatch(expected, found, span);
+            }
+        }
+    }
+
+    fn expect_assignable(&mut self, expected: &VarType, found: &StaticType, span: Span) {
+        if let Some(found) = found {
+            if !assignable(expected, found) {
+                self.mismatch(expected, found, span);
+            }
+        }
+    }
+
+    fn mismatch(&mut self, expected: &VarType, found: &VarType, span: Span) {
+        self.error(&format!("Type mismatch: expected {:?}, found {:?}", expected, found), span);
+    }
+
+    // Reports an invalid operation; its result type is unknown so the error does not cascade
+    fn invalid(&mut self, message: &str, span: Span) -> StaticType {
+        self.error(message, span);
+        None
+    }
+
+    fn error(&mut self, message: &str, span: Span) {
+        self.errors.push(TypeError::new(message, span));
+    }
+}
+
+// Whether running the statements always ends in a return or a throw. Loops only count when they
+// can only be left that way, as with while (true) and no break
+fn always_returns(statements: &[Spanned<AstNode>]) -> bool {
+    for statement in statements {
+        let returns = match &statement.node {
+            AstNode::Return(_) | AstNode::Throw(_) => true,
+            AstNode::Break | AstNode::Continue => return false,
+            AstNode::Block(statements) => always_returns(statements),
+            AstNode::If { then_branch, else_branch: Some(else_branch), .. } => {
+                always_returns(std::slice::from_ref(then_branch)) && always_returns(std::slice::from_ref(else_branch))
+            },
+            AstNode::While { condition, body } => condition.node == Expression::Bool(true) && !breaks(body),
+            AstNode::For { condition: None, body, .. } => !breaks(body),
+            AstNode::Switch { cases, default: Some(default), .. } => {
+                cases.iter().all(|case| always_returns(&case.body)) && always_returns(default)
+            },
+            AstNode::Try { body, catch, finally } => {
+                finally.as_deref().is_some_and(always_returns)
+                    || (always_returns(body) && catch.as_ref().is_none_or(|(_, handler)| always_returns(handler)))
+            },
+            _ => false,
+        };
+        if returns {
+            return true;
+        }
+    }
+    false
+}
+
+// Whether a loop body has a break that leaves this loop, rather than a nested loop or switch
+fn breaks(statement: &Spanned<AstNode>) -> bool {
+    match &statement.node {
+        AstNode::Break => true,
+        AstNode::Block(statements) => statements.iter().any(breaks),
+        AstNode::If { then_branch, else_branch, .. } => breaks(then_branch) || else_branch.as_deref().is_some_and(breaks),
+        AstNode::Try { body, catch, finally } => {
+            body.iter().any(breaks)
+                || catch.iter().any(|(_, handler)| handler.iter().any(breaks))
+                || finally.iter().flatten().any(breaks)
+        },
+        _ => false,
+    }
+}
+
+// Same widening rules as the interpreter's coerce; the byte range and the elements of
+// an untyped collection are only known at run time
+fn assignable(expected: &VarType, found: &VarType) -> bool {
+    match (found, expected) {
+        (VarType::Array(Some(found)), VarType::Array(Some(expected))) | (VarType::Object(Some(found)), VarType::Object(Some(expected))) => {
+            assignable(expected, found)
+        },
+        (VarType::Array(_), VarType::Array(_)) | (VarType::Object(_), VarType::Object(_)) => true,
+        _ => {
+            found == expected
+                || matches!(
+                    (found, expected),
+                    (VarType::Null, _)
+                        | (VarType::Int, VarType::Float)
+                        | (VarType::Byte, VarType::Int)
+                        | (VarType::Byte, VarType::Float)
+                        | (VarType::Int, VarType::Byte)
+                )
+        },
+    }
+}
+
+fn common_type(types: Vec<StaticType>) -> Option<Box<VarType>> {
+    let mut types = types.into_iter();
+    let first = types.next()??;
+    types.all(|other| other.as_ref() == Some(&first)).then(|| Box::new(first))
+}
+
+// The value of a literal case label, so that overlapping labels can be found before run time
+fn constant(expression: &Expression) -> Option<DynamicValue> {
+    match expression {
+        Expression::Number(Numeric::Int(value)) => Some(DynamicValue::Int(*value)),
+        Expression::Number(Numeric::Float(value)) => Some(DynamicValue::Float(*value)),
+        Expression::Number(Numeric::Byte(value)) => Some(DynamicValue::Int(*value as i64)),
+        Expression::StringLiteral(value) => Some(DynamicValue::String(value.clone())),
+        Expression::Char(value) => Some(DynamicValue::Char(*value)),
+        Expression::Bool(value) => Some(DynamicValue::Bool(*value)),
+        Expression::Unary { operator: UnaryOperator::Negate, operand } => match constant(&operand.node)? {
+            DynamicValue::Int(value) => value.checked_neg().map(DynamicValue::Int),
+            DynamicValue::Float(value) => Some(DynamicValue::Float(-value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Orders constants the way the interpreter compares them; None when they can never be equal
+fn label_order(left: &DynamicValue, right: &DynamicValue) -> Option<Ordering> {
+    match (left, right) {
+        (DynamicValue::Int(a), DynamicValue::Int(b)) => Some(a.cmp(b)),
+        (DynamicValue::String(a), DynamicValue::String(b)) => Some(a.cmp(b)),
+        (DynamicValue::Char(a), DynamicValue::Char(b)) => Some(a.cmp(b)),
+        (DynamicValue::Bool(a), DynamicValue::Bool(b)) => Some(a.cmp(b)),
+        (DynamicValue::Int(a), DynamicValue::Float(b)) => (*a as f64).partial_cmp(b),
+        (DynamicValue::Float(a), DynamicValue::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (DynamicValue::Float(a), DynamicValue::Float(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+fn describe(value: &DynamicValue) -> String {
+    match value {
+        DynamicValue::String(value) => format!("{:?}", value),
+        DynamicValue::Char(value) => format!("{:?}", value),
+        other => other.to_string(),
+    }
+}
+
+fn is_integer(var_type: &VarType) -> bool {
+    matches!(var_type, VarType::Int | VarType::Byte)
+}
+
+fn is_numeric(var_type: &VarType) -> bool {
+    matches!(var_type, VarType::Int | VarType::Float | VarType::Byte)
+}
@@ -0,0 +1,9 @@
+use crate::types::expression::Expression;
+use crate::types::spanned::Spanned;
+
+// An argument at a call site, either positional or named as in `add(b: 2)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: Option<String>,
+    pub value: Spanned<Expression>,
+}
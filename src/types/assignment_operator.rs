@@ -0,0 +1,8 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssignmentOperator {
+    Assign,         // =
+    AddAssign,      // +=
+    SubtractAssign, // -=
+    MultiplyAssign, // *=
+    DivideAssign,   // /=
+}
@@ -1,7 +1,9 @@
 use crate::types::ast_node::AstNode;
+use crate::types::spanned::Spanned;
 
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Ast {
-    pub statements: Vec<AstNode>,
+    pub statements: Vec<Spanned<AstNode>>,
 }
 
 impl Ast {
@@ -10,4 +12,4 @@ impl Ast {
             statements: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
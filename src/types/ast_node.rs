@@ -1,18 +1,51 @@
-use crate::types::tokens::Tokens;
-use crate::types::dynamic_value::DynamicValue;
+use crate::types::expression::Expression;
+use crate::types::signature::Signature;
+use crate::types::spanned::Spanned;
+use crate::types::switch_case::SwitchCase;
+use crate::types::var_type::VarType;
 
-pub struct AstNode {
-    pub token: Tokens,
-    pub left: Option<Box<DynamicValue>>,
-    pub right: Option<Box<DynamicValue>>,
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstNode {
+    VarDeclaration {
+        var_type: VarType,
+        name: String,
+        value: Option<Spanned<Expression>>,
+    },
+    Expression(Spanned<Expression>),
+    Block(Vec<Spanned<AstNode>>),
+    If {
+        condition: Spanned<Expression>,
+        then_branch: Box<Spanned<AstNode>>,
+        else_branch: Option<Box<Spanned<AstNode>>>,
+    },
+    While {
+        condition: Spanned<Expression>,
+        body: Box<Spanned<AstNode>>,
+    },
+    For {
+        initializer: Option<Box<Spanned<AstNode>>>,
+        condition: Option<Spanned<Expression>>,
+        update: Option<Spanned<Expression>>,
+        body: Box<Spanned<AstNode>>,
+    },
+    Switch {
+        subject: Spanned<Expression>,
+        cases: Vec<SwitchCase>,
+        default: Option<Vec<Spanned<AstNode>>>, // Runs when no case matches
+    },
+    Return(Option<Spanned<Expression>>),
+    Break,
+    Continue,
+    Try {
+        body: Vec<Spanned<AstNode>>,
+        catch: Option<(String, Vec<Spanned<AstNode>>)>, // The name the caught error is bound to, and the handler
+        finally: Option<Vec<Spanned<AstNode>>>,
+    },
+    Throw(Spanned<Expression>),
+    Function {
+        doc: Option<String>, // Joined `///` comments written directly above the function
+        name: String,
+        signature: Signature,
+        body: Vec<Spanned<AstNode>>,
+    },
 }
-
-impl AstNode {
-    pub fn new(token: Tokens, left: Option<DynamicValue>, right: Option<DynamicValue>) -> Self {
-        AstNode {
-            token,
-            left: left.map(Box::new),
-            right: right.map(Box::new),
-        }
-    }
-}
\ No newline at end of file
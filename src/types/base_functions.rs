@@ -7,12 +7,21 @@ pub enum BaseFunctions {
     Switch,
     Case,
     Function,
+    Return,
+    Break,
+    Continue,
+    Default,
+    Try,
+    Catch,
+    Finally,
+    Throw,
     WriteLn,
     ConvertTo,
     // Add other built-in functions and keywords as needed
 }
 
 impl BaseFunctions {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "if" => Some(BaseFunctions::If),
@@ -22,6 +31,14 @@ impl BaseFunctions {
             "switch" => Some(BaseFunctions::Switch),
             "case" => Some(BaseFunctions::Case),
             "fn" => Some(BaseFunctions::Function),
+            "return" => Some(BaseFunctions::Return),
+            "break" => Some(BaseFunctions::Break),
+            "continue" => Some(BaseFunctions::Continue),
+            "default" => Some(BaseFunctions::Default),
+            "try" => Some(BaseFunctions::Try),
+            "catch" => Some(BaseFunctions::Catch),
+            "finally" => Some(BaseFunctions::Finally),
+            "throw" => Some(BaseFunctions::Throw),
             "writeLn" => Some(BaseFunctions::WriteLn),
             "convertTo" => Some(BaseFunctions::ConvertTo),
             _ => None,
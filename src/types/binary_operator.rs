@@ -0,0 +1,19 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryOperator {
+    Add,            // +
+    Subtract,       // -
+    Multiply,       // *
+    Divide,         // /
+    Modulo,         // %
+    Equal,          // ==
+    NotEqual,       // !=
+    Less,           // <
+    LessEqual,      // <=
+    Greater,        // >
+    GreaterEqual,   // >=
+    And,            // &&
+    Or,             // ||
+    BitAnd,         // &
+    BitOr,          // |
+    BitXor,         // ^
+}
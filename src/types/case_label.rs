@@ -0,0 +1,21 @@
+use crate::types::expression::Expression;
+use crate::types::span::Span;
+use crate::types::spanned::Spanned;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseLabel {
+    Value(Spanned<Expression>),
+    Range {
+        start: Spanned<Expression>,
+        end: Spanned<Expression>, // Inclusive, so 'a'..'z' covers 'z'
+    },
+}
+
+impl CaseLabel {
+    pub fn span(&self) -> Span {
+        match self {
+            CaseLabel::Value(value) => value.span,
+            CaseLabel::Range { start, end } => start.span.to(end.span),
+        }
+    }
+}
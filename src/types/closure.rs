@@ -0,0 +1,12 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::types::function_definition::FunctionDefinition;
+use crate::types::variable::Variable;
+
+// A function used as a value: a lambda with the locals it captured when it was created,
+// or a named function, which captures nothing
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Arc<FunctionDefinition>,
+    pub captured: HashMap<String, Variable>,
+}
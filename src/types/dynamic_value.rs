@@ -1,17 +1,25 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 use crate::types::ast_node::AstNode;
+use crate::types::closure::Closure;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
 use crate::types::var_type::VarType;
 
+#[derive(Debug, Clone)]
 pub enum DynamicValue {
     Node(Box<AstNode>),
     Int(i64),
-    Long(f64),
+    Float(f64),
     String(String),
     Bool(bool),
     Char(char),
     Byte(u8),
     Array(Vec<DynamicValue>),
     Object(HashMap<String, DynamicValue>),
+    Function(Arc<Closure>),
+    Error(Box<RuntimeError>), // A caught error
     Null,
 }
 
@@ -30,13 +38,19 @@ impl DynamicValue {
         }
     }
 
-    pub fn as_long(&self) -> Option<f64> {
+    pub fn as_float(&self) -> Option<f64> {
         match self {
-            DynamicValue::Long(f) => Some(*f),
+            DynamicValue::Float(f) => Some(*f),
             _ => None,
         }
     }
 
+    /// The float accessor under its old name, from when the variant was called `Long`.
+    #[deprecated(note = "use `as_float`; `DynamicValue::Long` is now `DynamicValue::Float`")]
+    pub fn as_long(&self) -> Option<f64> {
+        self.as_float()
+    }
+
     pub fn as_string(&self) -> Option<&str> {
         match self {
             DynamicValue::String(s) => Some(s),
@@ -79,25 +93,304 @@ impl DynamicValue {
         }
     }
 
-    pub fn as_null(&self) -> bool {
+    pub fn is_function(&self) -> bool {
+        matches!(self, DynamicValue::Function(_))
+    }
+
+    pub fn as_error(&self) -> Option<&RuntimeError> {
         match self {
-            DynamicValue::Null => true,
-            _ => false,
+            DynamicValue::Error(e) => Some(e),
+            _ => None,
         }
     }
 
+    pub fn as_null(&self) -> bool {
+        matches!(self, DynamicValue::Null)
+    }
+
     pub fn get_type(&self) -> VarType {
         match self {
             DynamicValue::Node(_) => VarType::Node,
             DynamicValue::Int(_) => VarType::Int,
-            DynamicValue::Long(_) => VarType::Float,
+            DynamicValue::Float(_) => VarType::Float,
             DynamicValue::String(_) => VarType::String,
             DynamicValue::Bool(_) => VarType::Bool,
             DynamicValue::Char(_) => VarType::Char,
             DynamicValue::Byte(_) => VarType::Byte,
-            DynamicValue::Array(_) => VarType::Array,
-            DynamicValue::Object(_) => VarType::Object,
+            DynamicValue::Array(_) => VarType::Array(None),
+            DynamicValue::Object(_) => VarType::Object(None),
+            DynamicValue::Function(_) => VarType::Function,
+            DynamicValue::Error(_) => VarType::Error,
             DynamicValue::Null => VarType::Null,
         }
     }
-}
\ No newline at end of file
+
+    /// Converts the value to `target`, the same way `convertTo` does in a script.
+    ///
+    /// Every value converts to a string and to its own type, and `null` converts to every type
+    /// as `null`. Otherwise a conversion succeeds only when it keeps the value intact:
+    ///
+    /// - Numbers convert between `Int`, `Float` and `Byte` when the result is in range. Floats
+    ///   are truncated toward zero, and NaN or infinity never convert to an integer.
+    /// - `Bool` converts to and from numbers as 1 and 0. Any non-zero number is `true`.
+    /// - `Char` converts to and from `Int` and `Byte` by code point.
+    /// - Strings are parsed, ignoring surrounding whitespace. A string converts to a `Char` if it
+    ///   holds exactly one character, and to an `Array` of its characters.
+    /// - An `Object` converts to an `Array` of `[key, value]` pairs sorted by key, and such an
+    ///   array converts back to an `Object`.
+    /// - A typed collection such as `array<int>` converts every element to its element type.
+    ///
+    /// Anything else fails with [`RuntimeErrorKind::ConversionFailed`].
+    pub fn convert_to(&self, target: VarType) -> Result<DynamicValue, RuntimeError> {
+        let failed = |reason: &str| {
+            let value = match self {
+                DynamicValue::String(string) => format!("\"{}\"", string),
+                other => other.to_string(),
+            };
+            RuntimeError::new(RuntimeErrorKind::ConversionFailed { value, target: target.clone(), reason: reason.to_string() })
+        };
+        let unsupported = || failed(&format!("there is no conversion from {:?}", self.get_type()));
+
+        match (self, &target) {
+            (value, target) if value.get_type() == *target => Ok(value.clone()),
+            (_, VarType::String) => Ok(DynamicValue::String(self.to_string())),
+            (DynamicValue::Null, _) => Ok(DynamicValue::Null),
+
+            // A typed collection converts to the plain collection first, then converts each element
+            (_, VarType::Array(Some(element))) => match self.convert_to(VarType::Array(None))? {
+                DynamicValue::Array(values) => {
+                    let values = values.iter().map(|value| value.convert_to((**element).clone())).collect::<Result<Vec<_>, _>>()?;
+                    Ok(DynamicValue::Array(values))
+                },
+                _ => Err(unsupported()),
+            },
+            (_, VarType::Object(Some(element))) => match self.convert_to(VarType::Object(None))? {
+                DynamicValue::Object(values) => {
+                    let values = values
+                        .iter()
+                        .map(|(key, value)| value.convert_to((**element).clone()).map(|value| (key.clone(), value)))
+                        .collect::<Result<HashMap<_, _>, _>>()?;
+                    Ok(DynamicValue::Object(values))
+                },
+                _ => Err(unsupported()),
+            },
+
+            (DynamicValue::Byte(byte), VarType::Int) => Ok(DynamicValue::Int(*byte as i64)),
+            (DynamicValue::Bool(boolean), VarType::Int) => Ok(DynamicValue::Int(*boolean as i64)),
+            (DynamicValue::Char(ch), VarType::Int) => Ok(DynamicValue::Int(*ch as i64)),
+            (DynamicValue::Float(float), VarType::Int) => {
+                let truncated = float.trunc();
+                if !float.is_finite() {
+                    Err(failed("it is not a finite number"))
+                } else if truncated < i64::MIN as f64 || truncated >= i64::MAX as f64 {
+                    Err(failed("it is out of range"))
+                } else {
+                    Ok(DynamicValue::Int(truncated as i64))
+                }
+            },
+            (DynamicValue::String(string), VarType::Int) => {
+                string.trim().parse().map(DynamicValue::Int).map_err(|_| failed("it is not a whole number"))
+            },
+
+            (DynamicValue::Int(int), VarType::Float) => Ok(DynamicValue::Float(*int as f64)),
+            (DynamicValue::Byte(byte), VarType::Float) => Ok(DynamicValue::Float(*byte as f64)),
+            (DynamicValue::Bool(boolean), VarType::Float) => Ok(DynamicValue::Float(*boolean as i64 as f64)),
+            (DynamicValue::String(string), VarType::Float) => match string.trim().parse::<f64>() {
+                Ok(float) if float.is_finite() => Ok(DynamicValue::Float(float)),
+                _ => Err(failed("it is not a finite number")),
+            },
+
+            (DynamicValue::Int(_) | DynamicValue::Float(_) | DynamicValue::Char(_) | DynamicValue::String(_), VarType::Byte) => {
+                let int = self.convert_to(VarType::Int).map_err(|error| match error.kind {
+                    RuntimeErrorKind::ConversionFailed { reason, .. } => failed(&reason),
+                    _ => error,
+                })?;
+                match int {
+                    DynamicValue::Int(int) => u8::try_from(int).map(DynamicValue::Byte).map_err(|_| failed("it is out of range")),
+                    _ => Err(unsupported()),
+                }
+            },
+            (DynamicValue::Bool(boolean), VarType::Byte) => Ok(DynamicValue::Byte(*boolean as u8)),
+
+            (DynamicValue::Int(int), VarType::Bool) => Ok(DynamicValue::Bool(*int != 0)),
+            (DynamicValue::Byte(byte), VarType::Bool) => Ok(DynamicValue::Bool(*byte != 0)),
+            (DynamicValue::Float(float), VarType::Bool) if float.is_nan() => Err(failed("it is not a number")),
+            (DynamicValue::Float(float), VarType::Bool) => Ok(DynamicValue::Bool(*float != 0.0)),
+            (DynamicValue::String(string), VarType::Bool) => match string.trim() {
+                "true" => Ok(DynamicValue::Bool(true)),
+                "false" => Ok(DynamicValue::Bool(false)),
+                _ => Err(failed("only \"true\" and \"false\" convert to Bool")),
+            },
+
+            (DynamicValue::Int(int), VarType::Char) => u32::try_from(*int)
+                .ok()
+                .and_then(char::from_u32)
+                .map(DynamicValue::Char)
+                .ok_or_else(|| failed("it is not a valid code point")),
+            (DynamicValue::Byte(byte), VarType::Char) => Ok(DynamicValue::Char(*byte as char)),
+            (DynamicValue::String(string), VarType::Char) => {
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Ok(DynamicValue::Char(ch)),
+                    _ => Err(failed("it is not a single character")),
+                }
+            },
+
+            (DynamicValue::String(string), VarType::Array(None)) => Ok(DynamicValue::Array(string.chars().map(DynamicValue::Char).collect())),
+            (DynamicValue::Object(entries), VarType::Array(None)) => {
+                let mut keys = entries.keys().collect::<Vec<_>>();
+                keys.sort();
+                let pairs = keys
+                    .into_iter()
+                    .map(|key| DynamicValue::Array(vec![DynamicValue::String(key.clone()), entries[key].clone()]));
+                Ok(DynamicValue::Array(pairs.collect()))
+            },
+
+            (DynamicValue::Array(pairs), VarType::Object(None)) => pairs
+                .iter()
+                .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                    Some([DynamicValue::String(key), value]) => Ok((key.clone(), value.clone())),
+                    _ => Err(failed("it is not a list of [key, value] pairs with string keys")),
+                })
+                .collect::<Result<HashMap<_, _>, _>>()
+                .map(DynamicValue::Object),
+
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+// Structural equality, except that NaN equals NaN so any value can be compared in a test.
+// Scripts use their own `==`, which also compares numbers across types.
+impl PartialEq for DynamicValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DynamicValue::Node(a), DynamicValue::Node(b)) => a == b,
+            (DynamicValue::Int(a), DynamicValue::Int(b)) => a == b,
+            (DynamicValue::Float(a), DynamicValue::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (DynamicValue::String(a), DynamicValue::String(b)) => a == b,
+            (DynamicValue::Bool(a), DynamicValue::Bool(b)) => a == b,
+            (DynamicValue::Char(a), DynamicValue::Char(b)) => a == b,
+            (DynamicValue::Byte(a), DynamicValue::Byte(b)) => a == b,
+            (DynamicValue::Array(a), DynamicValue::Array(b)) => a == b,
+            (DynamicValue::Object(a), DynamicValue::Object(b)) => a == b,
+            // The same closure, or the same named function used as a value twice
+            (DynamicValue::Function(a), DynamicValue::Function(b)) => {
+                Arc::ptr_eq(a, b) || (Arc::ptr_eq(&a.function, &b.function) && a.captured.is_empty() && b.captured.is_empty())
+            },
+            (DynamicValue::Error(a), DynamicValue::Error(b)) => a == b,
+            (DynamicValue::Null, DynamicValue::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+// The text writeLn and interpolation produce. Strings and chars are quoted inside collections,
+// and object keys are sorted so the output is deterministic.
+impl fmt::Display for DynamicValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicValue::Node(_) => write!(f, "<node>"),
+            DynamicValue::Int(value) => write!(f, "{}", value),
+            DynamicValue::Float(value) => write!(f, "{:?}", value),
+            DynamicValue::String(value) => write!(f, "{}", value),
+            DynamicValue::Bool(value) => write!(f, "{}", value),
+            DynamicValue::Char(value) => write!(f, "{}", value),
+            DynamicValue::Byte(value) => write!(f, "{}", value),
+            DynamicValue::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, value)?;
+                }
+                write!(f, "]")
+            },
+            DynamicValue::Object(values) => {
+                let mut keys = values.keys().collect::<Vec<_>>();
+                keys.sort();
+                write!(f, "{{")?;
+                for (index, key) in keys.into_iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", key)?;
+                    write_element(f, &values[key])?;
+                }
+                write!(f, "}}")
+            },
+            DynamicValue::Function(closure) => write!(f, "<fn {}>", closure.function.name),
+            DynamicValue::Error(error) => write!(f, "{}: {}", error.kind.name(), error.kind),
+            DynamicValue::Null => write!(f, "null"),
+        }
+    }
+}
+
+fn write_element(f: &mut fmt::Formatter<'_>, value: &DynamicValue) -> fmt::Result {
+    match value {
+        DynamicValue::String(value) => write!(f, "{:?}", value),
+        DynamicValue::Char(value) => write!(f, "{:?}", value),
+        other => write!(f, "{}", other),
+    }
+}
+
+impl From<i64> for DynamicValue {
+    fn from(value: i64) -> Self {
+        DynamicValue::Int(value)
+    }
+}
+
+impl From<i32> for DynamicValue {
+    fn from(value: i32) -> Self {
+        DynamicValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for DynamicValue {
+    fn from(value: f64) -> Self {
+        DynamicValue::Float(value)
+    }
+}
+
+impl From<String> for DynamicValue {
+    fn from(value: String) -> Self {
+        DynamicValue::String(value)
+    }
+}
+
+impl From<&str> for DynamicValue {
+    fn from(value: &str) -> Self {
+        DynamicValue::String(value.to_string())
+    }
+}
+
+impl From<bool> for DynamicValue {
+    fn from(value: bool) -> Self {
+        DynamicValue::Bool(value)
+    }
+}
+
+impl From<char> for DynamicValue {
+    fn from(value: char) -> Self {
+        DynamicValue::Char(value)
+    }
+}
+
+impl From<u8> for DynamicValue {
+    fn from(value: u8) -> Self {
+        DynamicValue::Byte(value)
+    }
+}
+
+impl From<()> for DynamicValue {
+    fn from(_: ()) -> Self {
+        DynamicValue::Null
+    }
+}
+
+impl<T: Into<DynamicValue>> From<Option<T>> for DynamicValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(DynamicValue::Null, Into::into)
+    }
+}
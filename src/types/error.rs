@@ -0,0 +1,70 @@
+use std::fmt;
+use crate::types::lex_error::LexError;
+use crate::types::parse_error::ParseError;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::type_error::TypeError;
+
+/// Any failure reported by [`Engine`](crate::Engine), tagged with the stage that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The source contained malformed tokens. Every bad token is reported, in source order.
+    Lex(Vec<LexError>),
+    /// The tokens did not form a valid program.
+    Parse(ParseError),
+    /// The program is not well typed. Every problem found is reported, in source order.
+    Type(Vec<TypeError>),
+    /// The script failed while running.
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Lex(errors) => {
+                let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", messages.join("; "))
+            },
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Type(errors) => {
+                let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", messages.join("; "))
+            },
+            Error::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Lex(errors) => errors.first().map(|error| error as _),
+            Error::Parse(error) => Some(error),
+            Error::Type(errors) => errors.first().map(|error| error as _),
+            Error::Runtime(error) => Some(error),
+        }
+    }
+}
+
+impl From<Vec<LexError>> for Error {
+    fn from(errors: Vec<LexError>) -> Self {
+        Error::Lex(errors)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<Vec<TypeError>> for Error {
+    fn from(errors: Vec<TypeError>) -> Self {
+        Error::Type(errors)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use crate::types::argument::Argument;
+use crate::types::assignment_operator::AssignmentOperator;
+use crate::types::base_functions::BaseFunctions;
+use crate::types::binary_operator::BinaryOperator;
+use crate::types::function_definition::FunctionDefinition;
+use crate::types::interpolation_segment::InterpolationSegment;
+use crate::types::numeric::Numeric;
+use crate::types::spanned::Spanned;
+use crate::types::unary_operator::UnaryOperator;
+use crate::types::var_type::VarType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(Numeric),
+    StringLiteral(String),
+    Interpolated(Vec<InterpolationSegment>),
+    Bool(bool),
+    Char(char),
+    Null,
+    Identifier(String),
+    Array(Vec<Spanned<Expression>>),
+    Object(Vec<(String, Spanned<Expression>)>),
+    Index {
+        target: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+    },
+    Member {
+        target: Box<Spanned<Expression>>,
+        name: String,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Spanned<Expression>>,
+    },
+    // A left associative chain of operators sharing a precedence, such as `a + b - c`, kept flat
+    // so that long chains do not nest
+    Binary {
+        first: Box<Spanned<Expression>>,
+        rest: Vec<(BinaryOperator, Spanned<Expression>)>,
+    },
+    Assign {
+        target: Box<Spanned<Expression>>,
+        operator: AssignmentOperator,
+        value: Box<Spanned<Expression>>,
+    },
+    Call {
+        name: String,
+        arguments: Vec<Argument>,
+    },
+    Invoke {
+        target: Box<Spanned<Expression>>,
+        arguments: Vec<Argument>,
+    },
+    Lambda(Arc<FunctionDefinition>), // An expression body is stored as a single return statement
+    BaseCall {
+        function: BaseFunctions,
+        arguments: Vec<Spanned<Expression>>,
+    },
+    ConvertTo {
+        value: Box<Spanned<Expression>>,
+        target: VarType,
+    },
+}
+
+impl Expression {
+    // Whether this names something that can be assigned to: a variable, or an element or member of one
+    pub fn is_place(&self) -> bool {
+        match self {
+            Expression::Identifier(_) => true,
+            Expression::Index { target, .. } | Expression::Member { target, .. } => target.node.is_place(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use crate::types::dynamic_value::DynamicValue;
+
+// How a statement finished, so that break, continue and return can unwind through enclosing blocks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    Normal(DynamicValue), // Expression statements carry their value; everything else carries Null
+    Break,
+    Continue,
+    Return(DynamicValue),
+}
@@ -0,0 +1,52 @@
+// Parsed form of an interpolation format such as {price:.2} or {id:>08x}
+// Grammar: [align][0][width][.precision][radix], align is one of < > ^ and radix one of x X o b.
+// Width and precision go up to MAX_WIDTH, so a script cannot make one hole allocate gigabytes
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FormatSpec {
+    pub align: Option<char>,
+    pub zero_pad: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    pub radix: Option<char>,
+}
+
+impl FormatSpec {
+    pub const MAX_WIDTH: usize = 1024;
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        let mut spec = FormatSpec::default();
+        let mut rest = s;
+
+        if let Some(align) = rest.chars().next().filter(|ch| matches!(ch, '<' | '>' | '^')) {
+            spec.align = Some(align);
+            rest = &rest[1..];
+        }
+        if let Some(stripped) = rest.strip_prefix('0') {
+            spec.zero_pad = true;
+            rest = stripped;
+        }
+
+        let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+        if digits > 0 {
+            spec.width = Some(rest[..digits].parse().ok().filter(|width| *width <= Self::MAX_WIDTH)?);
+            rest = &rest[digits..];
+        }
+
+        if let Some(stripped) = rest.strip_prefix('.') {
+            let digits = stripped.len() - stripped.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            spec.precision = Some(stripped[..digits].parse().ok().filter(|precision| *precision <= Self::MAX_WIDTH)?);
+            rest = &stripped[digits..];
+        }
+
+        match rest {
+            "" => {},
+            "x" | "X" | "o" | "b" => spec.radix = rest.chars().next(),
+            _ => return None,
+        }
+        Some(spec)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::interpreter::{coerce, type_mismatch};
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::var_type::VarType;
+
+/// Converts a script value into a Rust argument of a typed native function.
+///
+/// Conversions follow the same widening rules as typed script parameters, so an
+/// `int` argument is accepted where `f64` is expected.
+pub trait FromDynamic: Sized {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError>;
+
+    /// The script type this conversion expects, which lets the type checker validate calls
+    /// before they run. `None`, the default, accepts any value.
+    fn var_type() -> Option<VarType> {
+        None
+    }
+}
+
+impl FromDynamic for DynamicValue {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        Ok(value)
+    }
+}
+
+impl FromDynamic for i64 {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match coerce(value, &VarType::Int)? {
+            DynamicValue::Int(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Int, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::Int)
+    }
+}
+
+impl FromDynamic for f64 {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match coerce(value, &VarType::Float)? {
+            DynamicValue::Float(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Float, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::Float)
+    }
+}
+
+impl FromDynamic for String {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match value {
+            DynamicValue::String(value) => Ok(value),
+            other => Err(type_mismatch(VarType::String, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::String)
+    }
+}
+
+impl FromDynamic for bool {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match value {
+            DynamicValue::Bool(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Bool, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::Bool)
+    }
+}
+
+impl FromDynamic for char {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match value {
+            DynamicValue::Char(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Char, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::Char)
+    }
+}
+
+impl FromDynamic for u8 {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match coerce(value, &VarType::Byte)? {
+            DynamicValue::Byte(value) => Ok(value),
+            other => Err(type_mismatch(VarType::Byte, &other)),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        Some(VarType::Byte)
+    }
+}
+
+/// `null` becomes `None`; anything else must convert to `T`.
+impl<T: FromDynamic> FromDynamic for Option<T> {
+    fn from_dynamic(value: DynamicValue) -> Result<Self, RuntimeError> {
+        match value {
+            DynamicValue::Null => Ok(None),
+            other => T::from_dynamic(other).map(Some),
+        }
+    }
+
+    fn var_type() -> Option<VarType> {
+        T::var_type()
+    }
+}
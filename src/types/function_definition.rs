@@ -0,0 +1,10 @@
+use crate::types::ast_node::AstNode;
+use crate::types::signature::Signature;
+use crate::types::spanned::Spanned;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub signature: Signature,
+    pub body: Vec<Spanned<AstNode>>,
+}
@@ -0,0 +1,11 @@
+use crate::types::spanned::Spanned;
+use crate::types::tokens::Tokens;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Literal(String),
+    Hole {
+        tokens: Vec<Spanned<Tokens>>,
+        format: Option<String>, // Text after the top-level ':' in {value:spec}
+    },
+}
@@ -0,0 +1,12 @@
+use crate::types::expression::Expression;
+use crate::types::format_spec::FormatSpec;
+use crate::types::spanned::Spanned;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationSegment {
+    Literal(String),
+    Hole {
+        expression: Spanned<Expression>,
+        format: Option<FormatSpec>,
+    },
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::from_dynamic::FromDynamic;
+use crate::types::native_function::NativeFunction;
+use crate::types::native_result::NativeResult;
+use crate::types::runtime_error::RuntimeError;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+use crate::types::var_type::VarType;
+
+/// Rust closures that can be registered with
+/// [`Engine::register_typed_fn`](crate::Engine::register_typed_fn).
+///
+/// Implemented for `Send + Sync` closures of up to five arguments whose arguments implement
+/// [`FromDynamic`] and whose return type implements [`NativeResult`]. `Args` is the
+/// tuple of argument types and only exists to tell the implementations apart.
+pub trait IntoNativeFunction<Args> {
+    fn into_native_function(self, name: &str) -> NativeFunction;
+
+    /// The script type of each argument, as given by [`FromDynamic::var_type`].
+    fn parameter_types() -> Vec<Option<VarType>>;
+}
+
+// Each argument type parameter doubles as the name of the local holding that argument
+macro_rules! impl_into_native_function {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg,)*> IntoNativeFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: NativeResult,
+            $($arg: FromDynamic,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_native_function(self, name: &str) -> NativeFunction {
+                let name = name.to_string();
+                Arc::new(move |arguments: &[DynamicValue]| {
+                    let expected = <[&str]>::len(&[$(stringify!($arg)),*]);
+                    if arguments.len() != expected {
+                        return Err(RuntimeError::new(RuntimeErrorKind::ArgumentCount {
+                            function: name.clone(),
+                            expected,
+                            found: arguments.len(),
+                        }));
+                    }
+                    let mut arguments = arguments.iter().cloned();
+                    $(let $arg = $arg::from_dynamic(arguments.next().unwrap())?;)*
+                    (self)($($arg),*).into_native_result()
+                })
+            }
+
+            fn parameter_types() -> Vec<Option<VarType>> {
+                vec![$($arg::var_type()),*]
+            }
+        }
+    };
+}
+
+impl_into_native_function!();
+impl_into_native_function!(A);
+impl_into_native_function!(A, B);
+impl_into_native_function!(A, B, C);
+impl_into_native_function!(A, B, C, D);
+impl_into_native_function!(A, B, C, D, E);
@@ -0,0 +1,28 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnknownCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidCharLiteral,
+    InvalidEscape(char),
+    InvalidInterpolation(String),
+    InvalidNumber(String),
+    NumberOutOfRange(String),
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::UnknownCharacter(ch) => write!(f, "Unknown character '{}'", ch),
+            LexErrorKind::UnterminatedString => write!(f, "Unterminated string literal"),
+            LexErrorKind::UnterminatedComment => write!(f, "Unterminated block comment"),
+            LexErrorKind::InvalidCharLiteral => write!(f, "Char literals must hold exactly one character"),
+            LexErrorKind::InvalidEscape(ch) => write!(f, "Invalid escape sequence '\\{}'", ch),
+            LexErrorKind::InvalidInterpolation(reason) => write!(f, "Invalid interpolation: {}", reason),
+            LexErrorKind::InvalidNumber(literal) => write!(f, "Invalid number literal {}", literal),
+            LexErrorKind::NumberOutOfRange(literal) => write!(f, "Number {} is out of range", literal),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+/// Resource budgets for a single script run. `None` leaves a resource unlimited.
+///
+/// Every limit aborts the run with its own [`RuntimeErrorKind`](crate::RuntimeErrorKind)
+/// variant. Time spent inside host functions counts against the timeout, but a host
+/// function that never returns cannot be interrupted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// Statements executed plus expressions evaluated.
+    pub max_operations: Option<u64>,
+    /// Nested calls to script and host functions.
+    pub max_call_depth: Option<usize>,
+    /// Statements and expressions in progress, counted across calls, and levels of arrays,
+    /// objects and closures within a single value.
+    pub max_nesting: Option<usize>,
+    /// Total bytes of string, array and object data produced over the run, counting every copy.
+    pub max_memory: Option<usize>,
+    /// Wall-clock time from the start of the run.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    // Deep nesting would overflow the host's stack, so it is capped out of the box. Measured in a
+    // debug build, a level of nesting takes up to 7 KB of stack and a call up to 15 KB, keeping the
+    // defaults around 1 MB, within the 2 MB Rust gives spawned threads; release builds use a quarter
+    fn default() -> Self {
+        Limits {
+            max_operations: None,
+            max_call_depth: Some(Limits::DEFAULT_CALL_DEPTH),
+            max_nesting: Some(Limits::DEFAULT_NESTING),
+            max_memory: None,
+            timeout: None,
+        }
+    }
+}
+
+impl Limits {
+    pub const DEFAULT_CALL_DEPTH: usize = 32;
+    pub const DEFAULT_NESTING: usize = 128;
+
+    /// No limits at all, including on call depth and nesting.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_operations: None,
+            max_call_depth: None,
+            max_nesting: None,
+            max_memory: None,
+            timeout: None,
+        }
+    }
+}
@@ -1,7 +1,41 @@
+pub mod argument;
+pub mod assignment_operator;
 pub mod ast;
 pub mod ast_node;
 pub mod base_functions;
+pub mod binary_operator;
+pub mod case_label;
+pub mod closure;
 pub mod dynamic_value;
+pub mod error;
+pub mod expression;
+pub mod flow;
+pub mod format_spec;
+pub mod from_dynamic;
+pub mod function_definition;
+pub mod interpolation_part;
+pub mod interpolation_segment;
+pub mod into_native_function;
+pub mod lex_error;
+pub mod lex_error_kind;
+pub mod limits;
+pub mod native_function;
+pub mod native_result;
 pub mod numeric;
+pub mod parameter;
+pub mod parse_error;
+pub mod permissions;
+pub mod runtime_error;
+pub mod runtime_error_kind;
+pub mod scope;
+pub mod script;
+pub mod signature;
+pub mod span;
+pub mod spanned;
+pub mod stack_frame;
+pub mod switch_case;
 pub mod tokens;
-pub mod var_type;
\ No newline at end of file
+pub mod type_error;
+pub mod unary_operator;
+pub mod var_type;
+pub mod variable;
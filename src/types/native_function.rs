@@ -0,0 +1,6 @@
+use std::sync::Arc;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::runtime_error::RuntimeError;
+
+// A host function as the interpreter calls it, after any typed wrapping
+pub type NativeFunction = Arc<dyn Fn(&[DynamicValue]) -> Result<DynamicValue, RuntimeError> + Send + Sync>;
@@ -0,0 +1,20 @@
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::runtime_error::RuntimeError;
+
+/// Return types accepted from typed native functions: any value convertible into
+/// a [`DynamicValue`], or a `Result` of one for functions that can fail.
+pub trait NativeResult {
+    fn into_native_result(self) -> Result<DynamicValue, RuntimeError>;
+}
+
+impl<T: Into<DynamicValue>> NativeResult for T {
+    fn into_native_result(self) -> Result<DynamicValue, RuntimeError> {
+        Ok(self.into())
+    }
+}
+
+impl<T: Into<DynamicValue>> NativeResult for Result<T, RuntimeError> {
+    fn into_native_result(self) -> Result<DynamicValue, RuntimeError> {
+        self.map(Into::into)
+    }
+}
@@ -2,4 +2,5 @@
 pub enum Numeric {
     Int(i64),
     Float(f64),
+    Byte(u8),
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+use crate::types::expression::Expression;
+use crate::types::spanned::Spanned;
+use crate::types::var_type::VarType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub var_type: Option<VarType>,
+    pub name: String,
+    pub default: Option<Spanned<Expression>>, // Evaluated at each call that leaves the parameter out
+}
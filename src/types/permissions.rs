@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+/// The capabilities a script run is granted.
+///
+/// Capabilities are dotted names such as `io.print`, `net` or `fs.read`. Granting a name
+/// also grants everything below it, so `fs` covers `fs.read` and `fs.write`. The default
+/// grants only [`Permissions::PRINT`], so `writeLn` works out of the box and nothing else does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permissions {
+    granted: HashSet<String>,
+    all: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions::none().grant(Permissions::PRINT)
+    }
+}
+
+impl Permissions {
+    /// Needed by the built-in `writeLn`.
+    pub const PRINT: &'static str = "io.print";
+
+    /// Grants nothing; even `writeLn` is denied.
+    pub fn none() -> Self {
+        Permissions {
+            granted: HashSet::new(),
+            all: false,
+        }
+    }
+
+    /// Grants every capability.
+    pub fn all() -> Self {
+        Permissions {
+            granted: HashSet::new(),
+            all: true,
+        }
+    }
+
+    /// Adds a capability and everything below it.
+    pub fn grant(mut self, capability: &str) -> Self {
+        self.granted.insert(capability.to_string());
+        self
+    }
+
+    /// Removes an earlier grant of exactly this name.
+    pub fn revoke(mut self, capability: &str) -> Self {
+        self.granted.remove(capability);
+        self
+    }
+
+    /// Returns whether `capability` or one of its parents has been granted.
+    pub fn allows(&self, capability: &str) -> bool {
+        if self.all {
+            return true;
+        }
+        let mut name = capability;
+        loop {
+            if self.granted.contains(name) {
+                return true;
+            }
+            match name.rfind('.') {
+                Some(dot) => name = &name[..dot],
+                None => return false,
+            }
+        }
+    }
+}
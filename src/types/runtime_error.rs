@@ -0,0 +1,49 @@
+use std::fmt;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+use crate::types::span::Span;
+use crate::types::stack_frame::StackFrame;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Option<Span>,
+    pub backtrace: Box<[StackFrame]>, // The calls in progress when the error was raised, innermost first
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind) -> Self {
+        RuntimeError { kind, span: None, backtrace: Box::default() }
+    }
+
+    // Keeps the innermost location when an error bubbles up through enclosing nodes
+    pub fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.kind, span),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+// Lets host functions fail with a plain message: `Err("no such customer".into())`
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        RuntimeError::new(RuntimeErrorKind::Native(message))
+    }
+}
+
+impl From<&str> for RuntimeError {
+    fn from(message: &str) -> Self {
+        RuntimeError::new(RuntimeErrorKind::Native(message.to_string()))
+    }
+}
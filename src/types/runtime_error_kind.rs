@@ -0,0 +1,112 @@
+use std::fmt;
+use std::time::Duration;
+use crate::types::var_type::VarType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    UndefinedVariable(String),
+    UndefinedFunction(String),
+    AlreadyDeclared(String),
+    TypeMismatch { expected: VarType, found: VarType },
+    InvalidOperation(String),
+    ArgumentCount { function: String, expected: usize, found: usize },
+    UnknownParameter { function: String, name: String },
+    DuplicateArgument { function: String, name: String },
+    MissingArgument { function: String, name: String },
+    ConversionFailed { value: String, target: VarType, reason: String },
+    IndexOutOfBounds { index: i64, length: usize },
+    DivisionByZero,
+    Overflow,
+    Native(String), // Raised by a host function
+    Thrown(String), // Raised by a script's `throw`
+    Serialization(String), // Converting between DynamicValue and a Rust type with serde
+    OperationLimitExceeded(u64),
+    CallDepthExceeded(usize),
+    NestingLimitExceeded(usize),
+    MemoryLimitExceeded(usize),
+    Timeout(Duration),
+    PermissionDenied { function: String, capability: String },
+}
+
+impl RuntimeErrorKind {
+    /// The variant's name, which scripts read as the `kind` of a caught error.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeErrorKind::UndefinedFunction(_) => "UndefinedFunction",
+            RuntimeErrorKind::AlreadyDeclared(_) => "AlreadyDeclared",
+            RuntimeErrorKind::TypeMismatch { .. } => "TypeMismatch",
+            RuntimeErrorKind::InvalidOperation(_) => "InvalidOperation",
+            RuntimeErrorKind::ArgumentCount { .. } => "ArgumentCount",
+            RuntimeErrorKind::UnknownParameter { .. } => "UnknownParameter",
+            RuntimeErrorKind::DuplicateArgument { .. } => "DuplicateArgument",
+            RuntimeErrorKind::MissingArgument { .. } => "MissingArgument",
+            RuntimeErrorKind::ConversionFailed { .. } => "ConversionFailed",
+            RuntimeErrorKind::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+            RuntimeErrorKind::DivisionByZero => "DivisionByZero",
+            RuntimeErrorKind::Overflow => "Overflow",
+            RuntimeErrorKind::Native(_) => "Native",
+            RuntimeErrorKind::Thrown(_) => "Thrown",
+            RuntimeErrorKind::Serialization(_) => "Serialization",
+            RuntimeErrorKind::OperationLimitExceeded(_) => "OperationLimitExceeded",
+            RuntimeErrorKind::CallDepthExceeded(_) => "CallDepthExceeded",
+            RuntimeErrorKind::NestingLimitExceeded(_) => "NestingLimitExceeded",
+            RuntimeErrorKind::MemoryLimitExceeded(_) => "MemoryLimitExceeded",
+            RuntimeErrorKind::Timeout(_) => "Timeout",
+            RuntimeErrorKind::PermissionDenied { .. } => "PermissionDenied",
+        }
+    }
+
+    /// Whether a script's `catch` can handle the error. Exceeding a limit always ends the run.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            RuntimeErrorKind::OperationLimitExceeded(_)
+                | RuntimeErrorKind::CallDepthExceeded(_)
+                | RuntimeErrorKind::NestingLimitExceeded(_)
+                | RuntimeErrorKind::MemoryLimitExceeded(_)
+                | RuntimeErrorKind::Timeout(_)
+        )
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::UndefinedVariable(name) => write!(f, "Undefined variable '{}'", name),
+            RuntimeErrorKind::UndefinedFunction(name) => write!(f, "Undefined function '{}'", name),
+            RuntimeErrorKind::AlreadyDeclared(name) => write!(f, "'{}' is already declared", name),
+            RuntimeErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "Type mismatch: expected {:?}, found {:?}", expected, found)
+            },
+            RuntimeErrorKind::InvalidOperation(message) => write!(f, "{}", message),
+            RuntimeErrorKind::ArgumentCount { function, expected, found } => {
+                write!(f, "'{}' expects {} argument(s), got {}", function, expected, found)
+            },
+            RuntimeErrorKind::UnknownParameter { function, name } => write!(f, "'{}' has no parameter '{}'", function, name),
+            RuntimeErrorKind::DuplicateArgument { function, name } => {
+                write!(f, "Argument '{}' is passed to '{}' more than once", name, function)
+            },
+            RuntimeErrorKind::MissingArgument { function, name } => write!(f, "'{}' is missing argument '{}'", function, name),
+            RuntimeErrorKind::ConversionFailed { value, target, reason } => {
+                write!(f, "Cannot convert {} to {:?}: {}", value, target, reason)
+            },
+            RuntimeErrorKind::IndexOutOfBounds { index, length } => {
+                write!(f, "Index {} is out of bounds for length {}", index, length)
+            },
+            RuntimeErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            RuntimeErrorKind::Overflow => write!(f, "Arithmetic overflow"),
+            RuntimeErrorKind::Native(message) => write!(f, "{}", message),
+            RuntimeErrorKind::Thrown(message) => write!(f, "{}", message),
+            RuntimeErrorKind::Serialization(message) => write!(f, "Serialization failed: {}", message),
+            RuntimeErrorKind::OperationLimitExceeded(limit) => write!(f, "Exceeded the limit of {} operations", limit),
+            RuntimeErrorKind::CallDepthExceeded(limit) => write!(f, "Exceeded the maximum call depth of {}", limit),
+            RuntimeErrorKind::NestingLimitExceeded(limit) => write!(f, "Exceeded the nesting limit of {} levels", limit),
+            RuntimeErrorKind::MemoryLimitExceeded(limit) => write!(f, "Exceeded the memory limit of {} bytes", limit),
+            RuntimeErrorKind::Timeout(timeout) => write!(f, "Timed out after {:?}", timeout),
+            RuntimeErrorKind::PermissionDenied { function, capability } => {
+                write!(f, "'{}' needs the '{}' capability, which this run was not granted", function, capability)
+            },
+        }
+    }
+}
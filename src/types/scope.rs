@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::variable::Variable;
+
+/// Global variables shared between the host and the scripts it runs.
+///
+/// Values set here are visible to scripts as globals. After a run, the scope holds
+/// the final value of every global, including ones the script declared itself.
+#[derive(Clone, Default)]
+pub struct Scope {
+    pub(crate) variables: HashMap<String, Variable>,
+}
+
+impl Scope {
+    /// Creates an empty scope.
+    pub fn new() -> Self {
+        Scope {
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Sets a global, replacing any earlier variable with the same name.
+    ///
+    /// Host variables are untyped, so scripts may assign them values of any type.
+    pub fn set(&mut self, name: &str, value: DynamicValue) {
+        self.variables.insert(name.to_string(), Variable { var_type: None, value });
+    }
+
+    /// Returns the current value of a global.
+    pub fn get(&self, name: &str) -> Option<&DynamicValue> {
+        self.variables.get(name).map(|variable| &variable.value)
+    }
+
+    /// Returns whether a global with this name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    /// Removes a global, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<DynamicValue> {
+        self.variables.remove(name).map(|variable| variable.value)
+    }
+}
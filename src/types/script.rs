@@ -0,0 +1,9 @@
+use crate::types::ast::Ast;
+
+/// A compiled script, produced by [`Engine::compile`](crate::Engine::compile).
+///
+/// Compiling once and running many times skips lexing and parsing on every run.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub(crate) ast: Ast,
+}
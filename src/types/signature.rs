@@ -0,0 +1,62 @@
+use crate::types::parameter::Parameter;
+use crate::types::runtime_error_kind::RuntimeErrorKind;
+use crate::types::var_type::VarType;
+
+// What a call site needs to know about a function, whether the script or the host defines it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<VarType>,
+}
+
+impl Signature {
+    // Host functions only take positional arguments, so their parameters have no names
+    pub fn positional(types: Vec<Option<VarType>>) -> Self {
+        let parameters = types
+            .into_iter()
+            .map(|var_type| Parameter { var_type, name: String::new(), default: None })
+            .collect();
+        Signature { parameters, return_type: None }
+    }
+
+    // Matches the arguments of a call, given by their names with positional ones first, to the parameters.
+    // Returns the index of the argument for each parameter, or None where the parameter's default applies
+    pub fn bind(&self, function: &str, names: &[Option<&str>]) -> Result<Vec<Option<usize>>, RuntimeErrorKind> {
+        let positional = names.iter().take_while(|name| name.is_none()).count();
+        if positional > self.parameters.len() {
+            return Err(RuntimeErrorKind::ArgumentCount {
+                function: function.to_string(),
+                expected: self.parameters.len(),
+                found: names.len(),
+            });
+        }
+
+        let mut slots: Vec<Option<usize>> = (0..self.parameters.len()).map(|index| (index < positional).then_some(index)).collect();
+        for (index, name) in names.iter().enumerate().skip(positional) {
+            let name = name.unwrap_or_default();
+            let parameter = self.parameters.iter().position(|parameter| !name.is_empty() && parameter.name == name);
+            match parameter {
+                None => return Err(RuntimeErrorKind::UnknownParameter { function: function.to_string(), name: name.to_string() }),
+                Some(parameter) if slots[parameter].is_some() => {
+                    return Err(RuntimeErrorKind::DuplicateArgument { function: function.to_string(), name: name.to_string() });
+                },
+                Some(parameter) => slots[parameter] = Some(index),
+            }
+        }
+
+        let missing = self.parameters.iter().zip(&slots).find(|(parameter, slot)| slot.is_none() && parameter.default.is_none());
+        match missing {
+            // Without named arguments this is simply too few of them
+            Some(_) if positional == names.len() => Err(RuntimeErrorKind::ArgumentCount {
+                function: function.to_string(),
+                expected: self.parameters.iter().filter(|parameter| parameter.default.is_none()).count(),
+                found: names.len(),
+            }),
+            Some((parameter, _)) => Err(RuntimeErrorKind::MissingArgument {
+                function: function.to_string(),
+                name: parameter.name.clone(),
+            }),
+            None => Ok(slots),
+        }
+    }
+}
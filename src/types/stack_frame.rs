@@ -0,0 +1,17 @@
+use std::fmt;
+use crate::types::span::Span;
+
+/// A function call that was in progress when a [`RuntimeError`](crate::RuntimeError) was raised.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// The called function; lambdas are called `lambda`.
+    pub function: String,
+    /// Where the script called it.
+    pub call_site: Span,
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} called at {}", self.function, self.call_site)
+    }
+}
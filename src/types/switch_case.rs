@@ -0,0 +1,9 @@
+use crate::types::ast_node::AstNode;
+use crate::types::case_label::CaseLabel;
+use crate::types::spanned::Spanned;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCase {
+    pub labels: Vec<CaseLabel>, // The arm runs when any label matches
+    pub body: Vec<Spanned<AstNode>>,
+}
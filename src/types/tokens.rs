@@ -1,29 +1,30 @@
 use crate::types::var_type::VarType;
 use crate::types::base_functions::BaseFunctions;
 use crate::types::numeric::Numeric;
+use crate::types::interpolation_part::InterpolationPart;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Tokens {
     Identifier(String),		        // Identifier
     StringLiteral(String),	        // String literal
+    InterpolatedString(Vec<InterpolationPart>), // $"text {expression:format}"
     NumberLiteral(Numeric),	        // Numeric literal
     BoolLiteral(bool),		        // Boolean literal
     CharLiteral(char),		        // Char literal
+    DocComment(String),		        // /// Doc comment
     Colon,  				        // :
     SemiColon,				        // ;
     Period,				            // .
     Equals,				            // =
     EqualEqual,				        // ==
-    EqualPlus,  			        // =+
-    EqualMinus, 			        // =-
-    EqualStar,				        // =*
-    EqualForwardSlash,		        // =/
+    FatArrow,				        // =>
     Plus,					        // +
     PlusPlus,   			        // ++
     PlusEqual,	    		        // +=
     Minus,					        // -
     MinusMinus,				        // --
     MinusEqual,				        // -=
+    Arrow,				            // ->
     Bang,					        // !
     NotEqual,				        // !=
     Star,					        // *
@@ -0,0 +1,26 @@
+use std::fmt;
+use crate::types::span::Span;
+
+/// A problem found by the static type check, before the script runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl TypeError {
+    pub fn new(message: &str, span: Span) -> Self {
+        TypeError {
+            message: message.to_string(),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+impl std::error::Error for TypeError {}
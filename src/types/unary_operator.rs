@@ -0,0 +1,10 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnaryOperator {
+    Not,            // !x
+    Negate,         // -x
+    Plus,           // +x
+    PreIncrement,   // ++x
+    PreDecrement,   // --x
+    PostIncrement,  // x++
+    PostDecrement,  // x--
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, PartialEq)]
+use std::fmt;
+
+#[derive(Clone, PartialEq)]
 pub enum VarType {
     Node,
     Int,
@@ -7,12 +9,19 @@ pub enum VarType {
     Bool,
     Char,
     Byte,
-    Array,
-    Object,
+    Array(Option<Box<VarType>>),  // array or array<int>, also written int[]
+    Object(Option<Box<VarType>>), // object or object<string, int>; keys are always strings
+    Function,
+    Error,
     Null,
 }
 
 impl VarType {
+    // Type names that came after the first release. They lex as identifiers, so scripts can still
+    // use them as names, and the parser reads them as types only where a type can go
+    pub const CONTEXTUAL: [&'static str; 3] = ["object", "function", "error"];
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "int" => Some(VarType::Int),
@@ -21,9 +30,50 @@ impl VarType {
             "bool" => Some(VarType::Bool),
             "char" => Some(VarType::Char),
             "byte" => Some(VarType::Byte),
-            "array" => Some(VarType::Array),
+            "array" => Some(VarType::Array(None)),
+            "object" => Some(VarType::Object(None)),
+            "function" => Some(VarType::Function),
+            "error" => Some(VarType::Error),
             "null" => Some(VarType::Null),
             _ => None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn array_of(element: VarType) -> Self {
+        VarType::Array(Some(Box::new(element)))
+    }
+
+    pub fn object_of(element: VarType) -> Self {
+        VarType::Object(Some(Box::new(element)))
+    }
+
+    // The type every element must have, if the collection declares one
+    pub fn element_type(&self) -> Option<&VarType> {
+        match self {
+            VarType::Array(Some(element)) | VarType::Object(Some(element)) => Some(element),
+            _ => None,
+        }
+    }
+}
+
+// Error messages show types this way, so element types read as Array<Int> rather than Array(Some(Int))
+impl fmt::Debug for VarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarType::Node => write!(f, "Node"),
+            VarType::Int => write!(f, "Int"),
+            VarType::Float => write!(f, "Float"),
+            VarType::String => write!(f, "String"),
+            VarType::Bool => write!(f, "Bool"),
+            VarType::Char => write!(f, "Char"),
+            VarType::Byte => write!(f, "Byte"),
+            VarType::Array(None) => write!(f, "Array"),
+            VarType::Array(Some(element)) => write!(f, "Array<{:?}>", element),
+            VarType::Object(None) => write!(f, "Object"),
+            VarType::Object(Some(element)) => write!(f, "Object<{:?}>", element),
+            VarType::Function => write!(f, "Function"),
+            VarType::Error => write!(f, "Error"),
+            VarType::Null => write!(f, "Null"),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use crate::types::dynamic_value::DynamicValue;
+use crate::types::var_type::VarType;
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub var_type: Option<VarType>, // None for untyped parameters
+    pub value: DynamicValue,
+}